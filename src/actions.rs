@@ -1,27 +1,223 @@
-use crates_io_api::{SyncClient, Error, ListOptions, Sort, ReverseDependencies, Meta};
+use super::APPNAME;
+
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use crates_io_api::{SyncClient, Crate, Error, ListOptions, Sort, ReverseDependencies,
+    ReverseDependency, Meta, User};
+use semver::{Version, VersionReq};
 use num_format::{Locale, ToFormattedString};
-use chrono::Local;
 use colored::*;
 
+use crate::cache::Cache;
+use crate::conf::{Config, Lists};
+use crate::feed::{self, Entry};
+use crate::license::{License, Verdict};
+use crate::manifest;
+use crate::registry::{self, Registry};
+use crate::watch::{Seen, Watch};
 use crate::util::*;
 
+/// Returns the compatible series of a version: `1.x`, `0.9.x` or `0.0.3`
+fn version_series(v: &Version) -> String {
+    match (v.major, v.minor) {
+        (0, 0) => format!("0.0.{}", v.patch),
+        (0, minor) => format!("0.{}.x", minor),
+        (major, _) => format!("{}.x", major),
+    }
+}
+
+/// Returns the range of some versions sorted from the newest, e.g. `1.0.0 - 1.2.3`
+fn version_range(versions: &[&Version]) -> String {
+    match (versions.last(), versions.first()) {
+        (Some(oldest), Some(newest)) if oldest != newest => format!("{} - {}", oldest, newest),
+        (Some(only), _) => only.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Returns the fields of a crate, as label and value, in the order shown.
+///
+/// The categories and the license are not included in the search results,
+/// so they are passed separately, when known.
+pub fn crate_fields(c: &Crate, categories: &[&str], license: Option<&String>)
+    -> Vec<(&'static str, String)> {
+
+    let mut fields = vec![("name", c.name.clone()), ("version", c.max_version.clone())];
+    if let Some(ref p) = c.description {
+        fields.push(("description", p.split_whitespace().collect::<Vec<_>>().join(" ")));
+    }
+    if !categories.is_empty() {
+        fields.push(("categories", categories.join(", ")));
+    }
+    if let Some(ref keywords) = c.keywords {
+        if !keywords.is_empty() { fields.push(("keywords", keywords.join(", "))); }
+    }
+    fields.push(("created", date_show(&c.created_at)));
+    fields.push(("updated", date_show(&c.updated_at)));
+    fields.push(("downloads", c.downloads.to_formatted_string(&Locale::en)));
+    if let Some(dl) = c.recent_downloads {
+        fields.push(("recent", dl.to_formatted_string(&Locale::en)));
+    }
+    if let Some(ref p) = c.repository { fields.push(("repository", p.clone())); }
+    if let (Some(p), Some(p2)) = (&c.homepage, &c.repository) {
+        if p != p2 { fields.push(("homepage", p.clone())); }
+    }
+    if let Some(ref p) = c.documentation { fields.push(("documentation", p.clone())); }
+    if let Some(l) = license {
+        fields.push(("license", l.clone()));
+    }
+    fields
+}
+
+/// A version of a crate that depends on another crate
+struct ReverseDep {
+    name: String,
+    version: String,
+    /// the version requirement of the dependency
+    req: String,
+    optional: bool,
+    created: Option<DateTime<Utc>>,
+    updated: Option<DateTime<Utc>>,
+    /// the downloads of the version
+    downloads: u64,
+    /// the downloads of the crate, when known
+    crate_downloads: Option<u64>,
+    size: Option<u64>,
+    license: Option<String>,
+}
+
+impl ReverseDep {
+    fn from_api(d: &ReverseDependency) -> ReverseDep {
+        ReverseDep {
+            name: d.crate_version.crate_name.clone(),
+            version: d.crate_version.num.clone(),
+            req: d.dependency.req.clone(),
+            optional: d.dependency.optional,
+            created: Some(d.crate_version.created_at),
+            updated: Some(d.crate_version.updated_at),
+            downloads: d.crate_version.downloads,
+            crate_downloads: None,
+            size: d.crate_version.crate_size,
+            license: d.crate_version.license.clone(),
+        }
+    }
+
+    /// From a dependency and the version of the dependent crate, as returned by the API
+    fn from_json(dependency: &serde_json::Value, version: &serde_json::Value) -> ReverseDep {
+        ReverseDep {
+            name: version["crate"].as_str().unwrap_or("?").to_string(),
+            version: version["num"].as_str().unwrap_or("?").to_string(),
+            req: dependency["req"].as_str().unwrap_or("?").to_string(),
+            optional: dependency["optional"].as_bool().unwrap_or(false),
+            created: registry::date(&version["created_at"]),
+            updated: registry::date(&version["updated_at"]),
+            downloads: version["downloads"].as_u64().unwrap_or(0),
+            crate_downloads: dependency["downloads"].as_u64(),
+            size: version["crate_size"].as_u64(),
+            license: version["license"].as_str().map(String::from),
+        }
+    }
+}
+
+/// An owner of a crate, either a user or a team
+struct Owner {
+    kind: String,
+    login: String,
+    /// the display name
+    name: Option<String>,
+    email: Option<String>,
+    /// the profile url
+    url: Option<String>,
+    is_team: bool,
+}
+
+impl Owner {
+    fn from_user(user: &User) -> Owner {
+        let kind = user.kind.clone().unwrap_or("user".to_string());
+        Owner {
+            // teams are named like github:org:team
+            is_team: kind == "team" || user.login.contains(':'),
+            kind,
+            login: user.login.clone(),
+            name: user.name.clone(),
+            email: user.email.clone(),
+            url: user.url.clone(),
+        }
+    }
+
+    fn from_json(owner: &serde_json::Value, kind: &str) -> Owner {
+        let text = |key: &str| owner[key].as_str().filter(|s| !s.is_empty()).map(String::from);
+        let login = text("login").unwrap_or("?".to_string());
+        Owner {
+            kind: text("kind").unwrap_or(kind.to_string()),
+            is_team: kind == "team" || login.contains(':'),
+            login,
+            name: text("name"),
+            email: text("email"),
+            url: text("url"),
+        }
+    }
+}
+
 pub struct Actuator {
-    client: SyncClient
+    client: SyncClient,
+    /// for the API endpoints that `client` doesn't cover,
+    /// only created when a command needs it
+    registry: RefCell<Option<Registry>>,
+    /// whether the warning that `client` ignores the `registry` setting was shown
+    warned: Cell<bool>,
 }
 
 impl Actuator {
 
     pub fn new() -> Actuator {
         Actuator {
-            client: SyncClient::new()
+            client: SyncClient::new(),
+            registry: RefCell::new(None),
+            warned: Cell::new(false),
+        }
+    }
+
+    /// Doesn't show the warning of `client`, for the interactive interface
+    pub fn silence_warnings(&self) {
+        self.warned.set(true);
+    }
+
+    /// Returns the client of the crates.io API.
+    ///
+    /// It can't be pointed to another registry, so when the `registry` setting
+    /// is a different one, a warning is shown the first time it's used.
+    fn client(&self) -> &SyncClient {
+        let registry = Config::current().registry;
+        if registry != Config::default().registry && !self.warned.replace(true) {
+            eprintln!("{} this command uses crates.io instead of the registry {}",
+                "Warning:".bright_red(), registry);
         }
+        &self.client
+    }
+
+    /// Returns the client of the registry API, creating it the first time,
+    /// so that the commands that work offline don't build one
+    fn registry(&self) -> Ref<Registry> {
+        if self.registry.borrow().is_none() {
+            *self.registry.borrow_mut() = Some(Registry::new());
+        }
+        Ref::map(self.registry.borrow(), |r| r.as_ref().expect("the registry was just created"))
     }
 
 
     /// Show a short general summary
     pub fn show_summary(&self) -> Result<(), Error> {
 
-        let summary = self.client.summary()?;
+        let summary = self.client().summary()?;
+        Cache::remember(summary.most_downloaded.iter()
+            .chain(summary.most_recently_downloaded.iter())
+            .chain(summary.just_updated.iter())
+            .chain(summary.new_crates.iter())
+            .map(|c| c.name.as_str()));
 
         println!("{}\t {}", "Total number of crates:".blue(),
             summary.num_crates.to_formatted_string(&Locale::en).bright_green());
@@ -31,30 +227,28 @@ impl Actuator {
         { // popular keywords
             let mut counter = 0_usize;
             println!("\n{}\n{}", "Popular keywords:".bright_blue(),
-                "# keyword crates_count created (ago)".bright_black());
+                "# keyword crates_count created".bright_black());
             for c in summary.popular_keywords.iter() {
                 counter += 1;
-                println!("{} {} {} {} {}",
+                println!("{} {} {} {}",
                     format!("{:2}", counter).bright_black(),
                     c.keyword.cyan(),
                     c.crates_cnt.to_formatted_string(&Locale::en).green(),
-                    date_str(&c.created_at, "").purple(),
-                    date_ago(&c.created_at, 1).purple(),
+                    date_show(&c.created_at).purple(),
                 );
             }
         }
         { // popular categories
             let mut counter = 0_usize;
             println!("\n{}\n{}", "Popular categories:".bright_blue(),
-                "# category crates_count created (ago)".bright_black());
+                "# category crates_count created".bright_black());
             for c in summary.popular_categories.iter() {
                 counter += 1;
-                println!("{} {} {} {} {}",
+                println!("{} {} {} {}",
                     format!("{:2}", counter).bright_black(),
                     c.category.cyan(),
                     c.crates_cnt.to_formatted_string(&Locale::en).green(),
-                    date_str(&c.created_at, "").purple(),
-                    date_ago(&c.created_at, 1).purple(),
+                    date_show(&c.created_at).purple(),
                 );
             }
 
@@ -63,17 +257,16 @@ impl Actuator {
         { // most downloaded
             let mut counter = 0_usize;
             println!("\n{}\n{}", "Most downloaded:".bright_blue(),
-                "# crate_name max_version downloads created updated (ago) description".bright_black());
+                "# crate_name max_version downloads created updated description".bright_black());
             for c in summary.most_downloaded.iter() {
                 counter += 1;
-                println!("{} {} {} {} {} {} {} {}",
+                println!("{} {} {} {} {} {} {}",
                     format!("{:2}", counter).bright_black(),
                     c.name.green(),
                     c.max_version.yellow(),
                     c.downloads.to_formatted_string(&Locale::en).red(),
-                    date_str(&c.created_at, "").purple(),
-                    date_str(&c.updated_at, "").bright_purple(),
-                    date_ago(&c.updated_at, 1).bright_purple(),
+                    date_show(&c.created_at).purple(),
+                    date_show(&c.updated_at).bright_purple(),
                     format!("{}", c.description.as_ref().unwrap().trim().replace('\n', ""))
                 );
             }
@@ -81,17 +274,16 @@ impl Actuator {
         { // most recently downloaded
             let mut counter = 0_usize;
             println!("\n{}\n{}", "Most recently downloaded:".bright_blue(),
-                "# crate_name max_version downloads created updated (ago) description".bright_black());
+                "# crate_name max_version downloads created updated description".bright_black());
             for c in summary.most_recently_downloaded.iter() {
                 counter += 1;
-                println!("{} {} {} {} {} {} {} {}",
+                println!("{} {} {} {} {} {} {}",
                     format!("{:2}", counter).bright_black(),
                     c.name.green(),
                     c.max_version.yellow(),
                     c.downloads.to_formatted_string(&Locale::en).red(),
-                    date_str(&c.created_at, "").purple(),
-                    date_str(&c.updated_at, "").bright_purple(),
-                    date_ago(&c.updated_at, 1).bright_purple(),
+                    date_show(&c.created_at).purple(),
+                    date_show(&c.updated_at).bright_purple(),
                     format!("{}", c.description.as_ref().unwrap().trim().replace('\n', ""))
                 );
             }
@@ -99,17 +291,16 @@ impl Actuator {
         { // just updated
             let mut counter = 0_usize;
             println!("\n{}\n{}", "Just updated:".bright_blue(),
-                "# crate_name max_version downloads created updated (ago) description".bright_black());
+                "# crate_name max_version downloads created updated description".bright_black());
             for c in summary.just_updated.iter() {
                 counter += 1;
-                println!("{} {} {} {} {} {} {} {}",
+                println!("{} {} {} {} {} {} {}",
                     format!("{:2}", counter).bright_black(),
                     c.name.green(),
                     c.max_version.yellow(),
                     c.downloads.to_formatted_string(&Locale::en).red(),
-                    date_str(&c.created_at, "").purple(),
-                    date_str(&c.updated_at, "").bright_purple(),
-                    date_ago(&c.updated_at, 1).bright_purple(),
+                    date_show(&c.created_at).purple(),
+                    date_show(&c.updated_at).bright_purple(),
                     format!("{}", c.description.as_ref().unwrap().trim().replace('\n', ""))
                 );
             }
@@ -117,17 +308,16 @@ impl Actuator {
         { // new crates
             let mut counter = 0_usize;
             println!("\n{}\n{}", "New crates:".bright_blue(),
-                "# crate_name max_version downloads created updated (ago) description".bright_black());
+                "# crate_name max_version downloads created updated description".bright_black());
             for c in summary.new_crates.iter() {
                 counter += 1;
-                println!("{} {} {} {} {} {} {} {}",
+                println!("{} {} {} {} {} {} {}",
                     format!("{:2}", counter).bright_black(),
                     c.name.green(),
                     c.max_version.yellow(),
                     c.downloads.to_formatted_string(&Locale::en).red(),
-                    date_str(&c.created_at, "").purple(),
-                    date_str(&c.updated_at, "").bright_purple(),
-                    date_ago(&c.updated_at, 1).bright_purple(),
+                    date_show(&c.created_at).purple(),
+                    date_show(&c.updated_at).bright_purple(),
                     format!("{}", c.description.as_ref().unwrap().trim().replace('\n', ""))
                 );
             }
@@ -139,7 +329,7 @@ impl Actuator {
     /// Show a more detailed summary of the new crates
     pub fn show_summary_new_crates(&self) -> Result<(), Error> {
 
-        let summary = self.client.summary()?;
+        let summary = self.client().summary()?;
 
         for c in summary.new_crates {
             // name & version
@@ -149,10 +339,8 @@ impl Actuator {
                 println!("\t{}\t{}", "description:".blue(), p.trim().replace("\n", "\n\t\t\t"));
             }
             // created at
-            println!("\t{}\t{} {}", "created:".blue(),
-                date_str(&c.created_at, "%Y-%m-%d %H:%M").purple(),
-                date_ago(&c.created_at, 1).bright_purple(),
-                );
+            println!("\t{}\t{}", "created:".blue(),
+                date_show(&c.created_at).purple());
             // categories (always null)
             // if let Some(ref p) = c.categories { println!("{}\t{:?}", "categories:".blue(), p); }
             // keywords (always null)
@@ -187,13 +375,15 @@ impl Actuator {
     pub fn search_crate(&self, query: Option<&str>, page: u64, per_page: u64)
         -> Result<(), Error> {
 
-        let res = self.client.crates(ListOptions{
+        let res = self.client().crates(ListOptions{
             sort: Sort::Alphabetical,
-            per_page: 100,
+            per_page,
             page,
             query: if let Some(q) = query { Some(q.to_string())} else {None},
         })?;
 
+        Cache::remember(res.crates.iter().map(|c| c.name.as_str()));
+
         println!("{} \"{}\"", "Searching for: ", query.unwrap_or("\"*\""));
 
         if res.crates.len() > 0 {
@@ -206,7 +396,7 @@ impl Actuator {
                     .to_string().bright_blue(),
 
                 // TODO: add sorted by
-                "# crate_name version created updated (ago) downloads (recent)"
+                "# crate_name version created updated downloads (recent)"
                 .bright_black());
         } else {
             println!("{}", "No results found.".red());
@@ -236,13 +426,12 @@ impl Actuator {
             }
 
             // 1st line
-            println!("{} {} {} {} {} {} {} {}",
+            println!("{} {} {} {} {} {} {}",
                 counter_str.bright_black(),
                 crate_name,
                 c.max_version.yellow(),
-                date_str(&c.created_at, "").purple(),
-                date_str(&c.updated_at, "").bright_purple(),
-                date_ago(&c.updated_at, 1).bright_purple(),
+                date_show(&c.created_at).purple(),
+                date_show(&c.updated_at).bright_purple(),
                 c.downloads.to_formatted_string(&Locale::en).red(),
                 recent_downloads,
             );
@@ -266,89 +455,121 @@ impl Actuator {
     }
 
 
-    /// Show crate information
-    pub fn show_crate(&self, crate_name: &str, show_rdeps: u64) -> Result<(), Error> {
-
-        let res = self.client.get_crate(crate_name)?;
-        let c = res.crate_data;
-
-        // name
-        println!("{}\t\t{}", "name:".blue(), c.name.green());
-        // version
-        println!("{}\t{}", "version:".blue() , c.max_version.yellow());
-        // description
-        if let Some(ref p) = c.description {
-            println!("{}\t{}", "description:".blue(), p.trim().replace("\n", "\n\t\t"));
-        }
-        // categories
-        if res.categories.len() > 0 {
-            let mut cat_str  = format!("{}", res.categories[0].category.cyan());
-            for cat in res.categories.iter().skip(1) {
-                cat_str = format!("{}, {}", cat_str, cat.category.cyan());
-            }
-            println!("{}\t{}", "categories:".blue(), cat_str);
-        }
-        // keywords
-        if let Some(ref keywlist) = c.keywords {
-            if keywlist.len() > 0 {
-                let mut keyw_str  = format!("{}", keywlist[0].cyan());
-                for keyw in keywlist.iter().skip(1) {
-                    keyw_str = format!("{}, {}", keyw_str, keyw.cyan());
-                }
-                println!("{}\t{}", "keywords:".blue(), keyw_str);
+    /// Show the owners section of a crate, a line per owner
+    fn print_owners(owners: &[Owner]) {
+        let users = owners.iter().filter(|o| !o.is_team).count();
+        println!("{}\t\t{} {}, {} {}", "owners:".blue(),
+            users, if users == 1 { "user" } else { "users" },
+            owners.len() - users, if owners.len() - users == 1 { "team" } else { "teams" });
+
+        let login_len = owners.iter().map(|o| o.login.len()).max().unwrap_or(0);
+        for o in owners {
+            let mut line = format!("\t{}\t{}", o.kind.bright_black(),
+                format!("{:w$}", o.login, w = login_len).yellow());
+            if let Some(ref name) = o.name {
+                line = format!("{}  {}", line, name);
+            }
+            if let Some(ref email) = o.email {
+                line = format!("{} <{}>", line, email.cyan());
+            }
+            if let Some(ref url) = o.url {
+                line = format!("{}  {}", line, url.bright_blue().underline());
             }
+            println!("{}", line);
         }
-        // created
-        println!("{}\t{} {}", "created:".blue(),
-            date_str(&c.created_at, "").purple(), date_ago(&c.created_at, 1).purple());
+    }
 
-        // updated
-        println!("{}\t{} {}", "updated:".blue(),
-            date_str(&c.updated_at, "").bright_purple(), date_ago(&c.updated_at, 1).bright_purple());
 
-        // downloads
-        println!("{}\t{}", "downloads:".blue(),
-            c.downloads.to_formatted_string(&Locale::en).red());
-        // repository
-        if let Some(ref p) = c.repository { println!("{}\t{}", "repository:".blue(),
-            p.bright_blue().underline()); }
-        // homepage (if != repository)
-        if let Some(ref p) = c.homepage {
-                if let Some(ref p2) = c.repository {
-                    if p != p2 {
-                        println!("{}\t{}", "homepage:".blue(),  p.bright_blue().underline());
-                    }
-                }
+    /// Returns one page of the crates matching a query, sorted by relevance,
+    /// and the total number of matches
+    pub fn find_crates(&self, query: Option<&str>, page: u64, per_page: u64)
+        -> Result<(Vec<Crate>, u64), Error> {
+
+        let res = self.client().crates(ListOptions{
+            sort: Sort::Relevance,
+            per_page,
+            page,
+            query: query.map(|q| q.to_string()),
+        })?;
+        Cache::remember(res.crates.iter().map(|c| c.name.as_str()));
+        Ok((res.crates, res.meta.total))
+    }
+
+
+    /// Returns the details of a crate, as label and value, in the order shown,
+    /// including its owners if `owners` is true
+    pub fn crate_details(&self, crate_name: &str, owners: bool)
+        -> Result<Vec<(&'static str, String)>, Error> {
+
+        let res = self.client().get_crate(crate_name)?;
+        Cache::remember(vec![res.crate_data.name.as_str()]);
+
+        let categories: Vec<&str> = res.categories.iter().map(|c| c.category.as_str()).collect();
+        let license = res.versions.get(0).and_then(|v| v.license.as_ref());
+        let mut details = crate_fields(&res.crate_data, &categories, license);
+
+        if owners {
+            if let Ok(owners) = self.client().crate_owners(crate_name) {
+                let owners: Vec<String> = owners.iter().map(|o| match o.name {
+                    Some(ref name) => format!("{} ({})", name, o.login),
+                    None => o.login.clone(),
+                }).collect();
+                details.push(("owners", owners.join(", ")));
+            }
         }
-        // documentation
-        if let Some(ref p) = c.documentation { println!("{}\t{}",
-            "documentation:".blue(), p.bright_blue().underline()); }
-        // license
-        if let Some(ref l) = res.versions[0].license { println!("{}\t{}",
-            "license:".blue(), l.bright_blue().italic()); }
+        Ok(details)
+    }
+
+
+    /// Show crate information
+    pub fn show_crate(&self, crate_name: &str, show_rdeps: u64, owners: bool) -> Result<(), Error> {
+
+        for (label, value) in self.crate_details(crate_name, false)? {
+            let value = match label {
+                "name" => value.green(),
+                "version" => value.yellow(),
+                "categories" | "keywords" => value.cyan(),
+                "created" => value.purple(),
+                "updated" => value.bright_purple(),
+                "downloads" => value.red(),
+                "repository" | "homepage" | "documentation" => value.bright_blue().underline(),
+                "license" => value.bright_blue().italic(),
+                _ => value.normal(),
+            };
+            let tabs = if label.len() < 7 { "\t\t" } else { "\t" };
+            println!("{}{}{}", format!("{}:", label).blue(), tabs, value);
+        }
+
         // owners
-        let owners = self.client.crate_owners(crate_name);
-        if let Ok(ref olist) = owners {
-            let mut ostr = "".to_string();
-            for o in olist {
-                if let Some(ref kind) = o.kind {
-                    match kind.as_ref() {
-                        "user" => ostr = format!("{}", ostr),
-                        "team" => ostr = format!("{}team: ", ostr),
-                        _ => () ,
+        if owners {
+            // the users and the teams are fetched separately, for the complete details
+            let users = self.registry().get(&format!("crates/{}/owner_user", crate_name));
+            let teams = self.registry().get(&format!("crates/{}/owner_team", crate_name));
+            match (users, teams) {
+                (Ok(users), Ok(teams)) => {
+                    let mut olist: Vec<Owner> = Vec::new();
+                    if let Some(list) = users["users"].as_array() {
+                        olist.extend(list.iter().map(|o| Owner::from_json(o, "user")));
                     }
-                }
-                if let Some(ref name) = o.name { ostr = format!("{}{}", ostr, name); }
-                if let Some(ref email) = o.email { ostr = format!("<{}> {}", ostr, email); }
-                ostr = format!("{} ({})\n\t\t", ostr, o.login);
+                    if let Some(list) = teams["teams"].as_array() {
+                        olist.extend(list.iter().map(|o| Owner::from_json(o, "team")));
+                    }
+                    Self::print_owners(&olist);
+                },
+                (Err(e), _) | (_, Err(e)) => println!("Error: couldn't fetch the owners: {}", e),
             }
-            println!("{}\t\t{}", "owners:".blue(), ostr.trim().yellow())
+        } else if let Ok(olist) = self.client().crate_owners(crate_name) {
+            let olist: Vec<Owner> = olist.iter().map(Owner::from_user).collect();
+            Self::print_owners(&olist);
         }
 
         // reverse dependencies
         let mut revdep = ReverseDependencies {dependencies: Vec::new(), meta: Meta {total:0}};
         if show_rdeps > 0 {
-            revdep = self.client.crate_reverse_dependencies(crate_name).unwrap();
+            match self.client().crate_reverse_dependencies(crate_name) {
+                Ok(r) => revdep = r,
+                Err(e) => println!("Error: couldn't fetch the reverse dependencies: {}", e),
+            }
             if revdep.meta.total > 0 {
                 println!("{}\t{}", "reverse deps:".blue(),
                     revdep.meta.total.to_formatted_string(&Locale::en).bright_green());
@@ -357,72 +578,969 @@ impl Actuator {
 
         // Show the list of reverse dependencies
         if show_rdeps > 1 && revdep.meta.total > 0 {
-            println!("{}",
-                "# optional crate_name version requires created updated (ago) downloads bytes (size) license"
-                .bright_black());
+            let deps: Vec<ReverseDep> = revdep.dependencies.iter()
+                .map(ReverseDep::from_api).collect();
+            Self::print_rdeps(&deps, 0, revdep.meta.total);
+        }
+        Ok(())
+    }
+
+
+    /// Show the reverse dependencies of a crate, a page at a time.
+    ///
+    /// They can be filtered to the optional ones, and to the ones whose requirement
+    /// matches a published version that also matches `req`, e.g. `^1` for the major 1.
+    /// They are sorted by downloads by the registry, so that only the pages needed
+    /// are fetched, unless they are sorted by name or update.
+    pub fn show_rdeps(&self, crate_name: &str, sort: &str, optional_only: bool,
+        req: Option<&str>, limit: usize, page: usize) -> Result<(), String> {
+
+        // the published versions matching the requirement
+        let matching: Option<Vec<Version>> = match req {
+            Some(req) => {
+                let req = VersionReq::parse(req)
+                    .map_err(|e| format!("invalid requirement \"{}\": {}", req, e))?;
+                let res = self.client().get_crate(crate_name).map_err(|e| e.to_string())?;
+                Some(res.versions.iter().filter_map(|v| Version::parse(&v.num).ok())
+                    .filter(|v| req.matches(v)).collect())
+            },
+            None => None,
+        };
+        let keep = |d: &ReverseDep| {
+            (!optional_only || d.optional) && matching.as_ref().map_or(true, |versions| {
+                VersionReq::parse(&d.req).map(|r| versions.iter().any(|v| r.matches(v)))
+                    .unwrap_or(false)
+            })
+        };
+
+        let wanted = if limit == 0 || sort != "downloads" { usize::max_value() } else { page * limit };
+        let (mut deps, total, complete) = self.fetch_rdeps(crate_name, wanted, keep)?;
+
+        match sort {
+            "name" => deps.sort_by(|a, b| a.name.cmp(&b.name)),
+            "updated" => deps.sort_by(|a, b| b.updated.cmp(&a.updated)),
+            _ => (), // already sorted by the registry
+        }
+
+        let start = (page - 1) * limit;
+        let end = if limit == 0 { deps.len() } else { (start + limit).min(deps.len()) };
+
+        if complete && (optional_only || req.is_some()) {
+            println!("{} of {} reverse dependencies of {} match.",
+                deps.len().to_formatted_string(&Locale::en).bright_green(),
+                total.to_formatted_string(&Locale::en).green(), crate_name.green());
+        } else {
+            println!("{} reverse dependencies of {}.",
+                total.to_formatted_string(&Locale::en).bright_green(), crate_name.green());
+        }
+        if start >= end {
+            println!("{}", "No results in this page.".red());
+            return Ok(());
+        }
+        println!("Showing {} to {}{}",
+            (start + 1).to_string().bright_blue(), end.to_string().bright_blue(),
+            if end < deps.len() || !complete {
+                format!(" (next page: --page {})", page + 1).bright_black().to_string()
+            } else {
+                String::new()
+            });
+
+        Self::print_rdeps(&deps[start..end], start, total);
+        Ok(())
+    }
+
+
+    /// Fetches the reverse dependencies of a crate that are kept by the filter,
+    /// in the order of the registry, until there are at least `wanted` of them.
+    ///
+    /// Returns them with the total number of reverse dependencies,
+    /// and whether all of them were fetched.
+    fn fetch_rdeps<F>(&self, crate_name: &str, wanted: usize, keep: F)
+        -> Result<(Vec<ReverseDep>, u64, bool), String> where F: Fn(&ReverseDep) -> bool {
+
+        use crossterm::tty::IsTty;
+
+        let mut deps: Vec<ReverseDep> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let (pairs, total) = self.registry().reverse_dependencies(crate_name, page)?;
+            deps.extend(pairs.iter().map(|(d, v)| ReverseDep::from_json(d, v)).filter(|d| keep(d)));
+
+            if pairs.is_empty() || page * registry::PER_PAGE >= total {
+                return Ok((deps, total, true));
+            }
+            if deps.len() >= wanted {
+                return Ok((deps, total, false));
+            }
+            // only on a terminal, not in a log of a json or csv output
+            if page == 1 && std::io::stderr().is_tty() {
+                eprintln!("Fetching {} reverse dependencies…", total.to_formatted_string(&Locale::en));
+            }
+            page += 1;
+        }
+    }
+
+
+    /// Show how many reverse dependencies of a crate, and how many of their downloads,
+    /// depend on each compatible series of its versions (e.g. `1.x`, `0.9.x`), bucketed
+    /// by the newest published version that satisfies their requirement
+    pub fn show_impact(&self, crate_name: &str) -> Result<(), String> {
+
+        let res = self.client().get_crate(crate_name).map_err(|e| e.to_string())?;
+        let mut versions: Vec<Version> = res.versions.iter().filter(|v| !v.yanked)
+            .filter_map(|v| Version::parse(&v.num).ok()).collect();
+        versions.sort();
+        versions.reverse();
+
+        let (deps, total, _) = self.fetch_rdeps(crate_name, usize::max_value(), |_| true)?;
+
+        // the dependents and their downloads by series, from the newest series
+        let mut buckets: Vec<(String, Vec<&Version>, u64, u64)> = Vec::new();
+        for v in versions.iter() {
+            let series = version_series(v);
+            match buckets.iter_mut().find(|b| b.0 == series) {
+                Some(bucket) => bucket.1.push(v),
+                None => buckets.push((series, vec![v], 0, 0)),
+            }
+        }
+        let mut unmatched = (0_u64, 0_u64);
+
+        for d in deps.iter() {
+            let newest = VersionReq::parse(&d.req).ok()
+                .and_then(|req| versions.iter().find(|v| req.matches(v)));
+            let downloads = d.crate_downloads.unwrap_or(d.downloads);
+            let series = newest.map(version_series);
+            match series.and_then(|s| buckets.iter().position(|b| b.0 == s)) {
+                Some(i) => { buckets[i].2 += 1; buckets[i].3 += downloads; },
+                None => { unmatched.0 += 1; unmatched.1 += downloads; },
+            }
+        }
+
+        let dependents = deps.len() as u64;
+        let downloads: u64 = buckets.iter().map(|b| b.3).sum::<u64>() + unmatched.1;
+        println!("Impact of {} on its {} reverse dependencies, with {} downloads:\n",
+            crate_name.green(), total.to_formatted_string(&Locale::en).bright_green(),
+            downloads.to_formatted_string(&Locale::en).red());
+        if dependents == 0 {
+            return Ok(());
+        }
+
+        println!("{}", "# series versions dependents (share) downloads (share)".bright_black());
+        let pct = |n: u64, of: u64| if of > 0 { n as f64 / of as f64 } else { 0.0 };
+        let series_len = buckets.iter().map(|b| b.0.len()).max().unwrap_or(0).max(4);
+        let range_len = buckets.iter().map(|b| version_range(&b.1).len()).max().unwrap_or(0);
+
+        let rows = buckets.iter().filter(|b| b.2 > 0)
+            .map(|b| (b.0.as_str(), version_range(&b.1), b.2, b.3))
+            .chain(if unmatched.0 > 0 {
+                Some(("none", "(no version matches)".to_string(), unmatched.0, unmatched.1))
+            } else { None });
+
+        for (series, range, count, dls) in rows {
+            println!("{} {} {} {} {}",
+                format!("{:w$}", series, w = series_len).yellow(),
+                format!("{:w$}", range, w = range_len).bright_black(),
+                format!("{:>7} ({:5.1}%)", count, pct(count, dependents) * 100.0).green(),
+                format!("{:>15} ({:5.1}%)", dls.to_formatted_string(&Locale::en),
+                    pct(dls, downloads) * 100.0).red(),
+                bar(pct(dls, downloads), 30).bright_red());
+        }
+
+        // the dependents that can't use the newest series, not counting the unmatched ones
+        if let Some(newest) = buckets.first() {
+            let stuck: u64 = buckets.iter().skip(1).map(|b| b.2).sum();
+            let stuck_dls: u64 = buckets.iter().skip(1).map(|b| b.3).sum();
+            println!("\n{} dependents ({:.1}% of the downloads) require an older series than {}.",
+                stuck.to_string().bright_yellow(), pct(stuck_dls, downloads) * 100.0,
+                newest.0.yellow());
+        }
+        if unmatched.0 > 0 {
+            println!("{} dependents ({:.1}% of the downloads) have a requirement \
+                that matches no published version.",
+                unmatched.0.to_string().bright_yellow(), pct(unmatched.1, downloads) * 100.0);
+        }
+        Ok(())
+    }
+
+
+    /// Show a table of reverse dependencies, numbered from `first` + 1
+    fn print_rdeps(deps: &[ReverseDep], first: usize, total: u64) {
+        println!("{}",
+            "# optional crate_name version requires created updated downloads bytes (size) license"
+            .bright_black());
+
+        let counter_len = total.to_string().len();
+
+        for (i, d) in deps.iter().enumerate() {
+            let mut size_bytes = "?".to_string().black();
+            let mut size = "?".to_string().black();
+            if let Some(s) = d.size {
+                size_bytes = s.to_string().blue();
+                size = format!("({})",
+                    byte_unit::Byte::from_bytes(s as u128).get_appropriate_unit(true)
+                    .to_string().replace(" ", "")).blue();
+            }
+
+            let mut license = "?".to_string().black();
+            if let Some(l) = &d.license {
+                license = l.bright_blue().italic();
+            }
+
+            let optional_str;
+            if d.optional {
+                optional_str = "O".bright_yellow();
+            } else {
+                optional_str = ".".black();
+            }
+
+            let created = d.created.as_ref().map(date_show).unwrap_or("?".to_string());
+            let updated = d.updated.as_ref().map(date_show).unwrap_or("?".to_string());
+
+            println!("{} {} {} {} {} {} {} {} {} {} {}",
+                format!("{:width$}", first + i + 1, width = counter_len).bright_black(),
+                optional_str,
+                d.name.green(),
+                d.version.yellow(),
+                d.req.bright_yellow(),
+                created.purple(),
+                updated.bright_purple(),
+                d.downloads.to_string().red(),
+                size_bytes,
+                size,
+                license,
+            );
+        }
+    }
+
+
+    /// Compare several crates side by side, in a column per crate,
+    /// highlighting the best value of each row
+    pub fn compare_crates(&self, names: &[&str]) -> Result<(), Error> {
 
-            let mut counter = 0;
-            let mut counter_str;
-            let counter_len = revdep.meta.total.to_string().len();
-
-            for d in revdep.dependencies.iter() {
-                let vreq = &d.dependency.req;
-                let name = &d.crate_version.crate_name;
-                let vnum = &d.crate_version.num;
-                let downloads = d.crate_version.downloads;
-
-                let mut size_bytes = "?".to_string().black();
-                let mut size = "?".to_string().black();
-                if let Some(s) = d.crate_version.crate_size {
-                    size_bytes = s.to_string().blue();
-                    size = format!("({})",
-                        byte_unit::Byte::from_bytes(s as u128).get_appropriate_unit(true)
-                        .to_string().replace(" ", "")).blue();
+        // the label of each row, and whether a higher value is better
+        const ROWS: [(&str, Option<bool>); 10] = [
+            ("downloads", Some(true)),
+            ("recent downloads", Some(true)),
+            ("version", None),
+            ("age", Some(true)),
+            ("last update", Some(true)),
+            ("license", None),
+            ("owners", Some(true)),
+            ("reverse deps", Some(true)),
+            ("dependencies", Some(false)),
+            ("crate size", Some(false)),
+        ];
+
+        // the text and the value compared of each cell, by column
+        let mut columns: Vec<(String, Vec<(String, Option<i64>)>)> = Vec::new();
+
+        for name in names {
+            let res = match self.client().get_crate(name) {
+                Ok(res) => res,
+                Err(e) => {
+                    println!("Error: couldn't fetch the crate \"{}\": {}", name.red(), e);
+                    continue;
                 }
+            };
+            let c = res.crate_data;
+            Cache::remember(vec![c.name.as_str()]);
+
+            let version = res.versions.iter().find(|v| v.num == c.max_version)
+                .or(res.versions.get(0));
+            let owners = self.client().crate_owners(name).ok().map(|o| o.len() as i64);
+            // the first page is enough to get the total
+            let rdeps = self.registry().reverse_dependencies(name, 1).ok()
+                .map(|(_, total)| total as i64);
+            let deps = self.client().crate_dependencies(name, &c.max_version).ok()
+                .map(|d| d.iter().filter(|d| d.kind == "normal").count() as i64);
+            let size = version.and_then(|v| v.crate_size);
+
+            let number = |n: Option<i64>| n.map_or("?".to_string(),
+                |n| n.to_formatted_string(&Locale::en));
+
+            columns.push((c.name.clone(), vec![
+                (number(Some(c.downloads as i64)), Some(c.downloads as i64)),
+                (number(c.recent_downloads.map(|d| d as i64)), c.recent_downloads.map(|d| d as i64)),
+                (c.max_version.clone(), None),
+                (date_show(&c.created_at), Some(-c.created_at.timestamp())),
+                (date_show(&c.updated_at), Some(c.updated_at.timestamp())),
+                (version.and_then(|v| v.license.clone()).unwrap_or("?".to_string()), None),
+                (number(owners), owners),
+                (number(rdeps), rdeps),
+                (number(deps), deps),
+                (size.map_or("?".to_string(), |s| byte_unit::Byte::from_bytes(s as u128)
+                    .get_appropriate_unit(true).to_string()), size.map(|s| s as i64)),
+            ]));
+        }
+
+        if columns.is_empty() {
+            return Ok(());
+        }
+
+        let label_width = ROWS.iter().map(|r| r.0.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = columns.iter().map(|(name, cells)| {
+            cells.iter().map(|c| c.0.chars().count()).chain(Some(name.len())).max().unwrap_or(0)
+        }).collect();
 
-                let mut license = "?".to_string().black();
-                if let Some(l) = &d.crate_version.license {
-                    license = l.bright_blue().italic();
+        // header
+        print!("{:w$}", "", w = label_width);
+        for ((name, _), width) in columns.iter().zip(widths.iter()) {
+            print!("  {}", format!("{:w$}", name, w = width).green());
+        }
+        println!();
+
+        for (row, (label, higher)) in ROWS.iter().enumerate() {
+            let values = columns.iter().filter_map(|(_, cells)| cells[row].1);
+            let best = match higher {
+                Some(true) => values.max(),
+                Some(false) => values.min(),
+                None => None,
+            };
+            // don't highlight the row when all the values are the same
+            let distinct = columns.iter().any(|(_, cells)| cells[row].1 != columns[0].1[row].1);
+
+            print!("{}", format!("{:w$}", label, w = label_width).blue());
+            for ((_, cells), width) in columns.iter().zip(widths.iter()) {
+                let cell = format!("{:w$}", cells[row].0, w = width);
+                if distinct && best.is_some() && cells[row].1 == best {
+                    print!("  {}", cell.bright_green().bold());
+                } else {
+                    print!("  {}", cell);
                 }
+            }
+            println!();
+        }
+        Ok(())
+    }
+
 
-                let optional = d.dependency.optional;
-                let optional_str;
-                if optional {
-                    optional_str = "O".bright_yellow();
+    /// Show the daily downloads of a crate over the last days (up to 90),
+    /// as text with sparklines, or exported as CSV or JSON
+    pub fn show_downloads(&self, crate_name: &str, days: i64, by_version: bool, format: &str)
+        -> Result<(), Error> {
+
+        let res = self.client().get_crate(crate_name)?;
+        let downloads = self.client().crate_downloads(crate_name)?;
+        Cache::remember(vec![res.crate_data.name.as_str()]);
+
+        let versions: HashMap<u64, &str> = res.versions.iter()
+            .map(|v| (v.id, v.num.as_str())).collect();
+
+        // the most recent day with data, since the current one is usually missing
+        let last = downloads.version_downloads.iter().map(|d| d.date)
+            .chain(downloads.meta.extra_downloads.iter().map(|d| d.date))
+            .max().unwrap_or_else(|| Utc::today().naive_utc());
+        let first = last - Duration::days(days - 1);
+        let dates: Vec<NaiveDate> = (0..days).map(|i| first + Duration::days(i)).collect();
+
+        // the daily downloads of all the versions, and of each one.
+        // the downloads of the versions not listed are counted as "other"
+        let mut daily = vec![0_u64; days as usize];
+        let mut by_ver: Vec<(String, Vec<u64>)> = Vec::new();
+        {
+            let mut count = |version: &str, date: NaiveDate, n: u64| {
+                let day = (date - first).num_days();
+                if day < 0 || day >= days { return; }
+                daily[day as usize] += n;
+                let pos = match by_ver.iter().position(|v| v.0 == version) {
+                    Some(pos) => pos,
+                    None => {
+                        by_ver.push((version.to_string(), vec![0; days as usize]));
+                        by_ver.len() - 1
+                    }
+                };
+                by_ver[pos].1[day as usize] += n;
+            };
+            for d in downloads.version_downloads.iter() {
+                count(versions.get(&d.version).cloned().unwrap_or("other"), d.date, d.downloads);
+            }
+            for d in downloads.meta.extra_downloads.iter() {
+                count("other", d.date, d.downloads);
+            }
+        }
+        by_ver.sort_by(|a, b| b.1.iter().sum::<u64>().cmp(&a.1.iter().sum::<u64>()));
+        let total: u64 = daily.iter().sum();
+
+        match format {
+            "csv" => {
+                if by_version {
+                    println!("date,version,downloads");
+                    for (i, date) in dates.iter().enumerate() {
+                        for (version, counts) in by_ver.iter() {
+                            println!("{},{},{}", date, version, counts[i]);
+                        }
+                    }
                 } else {
-                    optional_str = ".".black();
+                    println!("date,downloads");
+                    for (date, n) in dates.iter().zip(daily.iter()) {
+                        println!("{},{}", date, n);
+                    }
                 }
+            },
+            "json" => {
+                let series = |counts: &[u64]| -> Vec<serde_json::Value> {
+                    dates.iter().zip(counts.iter()).map(|(date, n)| serde_json::json!({
+                        "date": date.to_string(), "downloads": n })).collect()
+                };
+                let json = serde_json::json!({
+                    "crate": res.crate_data.name,
+                    "from": first.to_string(),
+                    "to": last.to_string(),
+                    "downloads": total,
+                    "daily": series(&daily),
+                    "versions": by_ver.iter().map(|(version, counts)| {
+                        let mut v = serde_json::json!({
+                            "version": version,
+                            "downloads": counts.iter().sum::<u64>(),
+                        });
+                        if by_version { v["daily"] = series(counts).into(); }
+                        v
+                    }).collect::<Vec<_>>(),
+                });
+                println!("{}", serde_json::to_string_pretty(&json)
+                    .expect("Error: couldn't serialize the downloads."));
+            },
+            _ => {
+                println!("Downloads of {} in the last {} days ({} to {}):",
+                    res.crate_data.name.green(), days, first, last);
+                println!("{}\t{}", "total:".blue(), total.to_formatted_string(&Locale::en).red());
+                println!("{}\t{}", "daily:".blue(),
+                    sparkline(&daily, daily.iter().cloned().max().unwrap_or(0)).bright_red());
 
-                let created = date_str(&d.crate_version.created_at, "");
-                let updated = date_str(&d.crate_version.updated_at, "");
-                //let updated = d.crate_version.updated_at.with_timezone(&Local);
-                let updated_ago = Local::now().
-                    signed_duration_since(d.crate_version.updated_at).to_std().unwrap();
-                let updated_ago_str = format!("({})",
-                    timeago::format_5chars(updated_ago).to_string());
-                // let updated_ago_str = format!("({})", timeago::Formatter::new().num_items(1).abbreviate().ago("").convert(updated_ago).to_string());
+                // week over week
+                if days >= 14 {
+                    let len = daily.len();
+                    let this_week: u64 = daily[len - 7..].iter().sum();
+                    let prev_week: u64 = daily[len - 14..len - 7].iter().sum();
+                    let growth = if prev_week > 0 {
+                        let pct = (this_week as f64 - prev_week as f64) / prev_week as f64 * 100.0;
+                        let pct_str = format!("{:+.1}%", pct);
+                        if pct >= 0.0 { pct_str.green() } else { pct_str.red() }
+                    } else {
+                        "?".bright_black()
+                    };
+                    println!("{}\t{} ({} vs {} the week before)", "weekly:".blue(), growth,
+                        this_week.to_formatted_string(&Locale::en),
+                        prev_week.to_formatted_string(&Locale::en));
+                }
 
-                counter += 1;
-                counter_str = format!("{:width$}", counter, width = counter_len);
-
-                println!("{} {} {} {} {} {} {} {} {} {} {} {}",
-                    counter_str.bright_black(),
-                    optional_str,
-                    name.green(),
-                    vnum.yellow(),
-                    vreq.bright_yellow(),
-                    created.purple(),
-                    updated.bright_purple(),
-                    updated_ago_str.bright_purple(),
-                    downloads.to_string().red(),
-                    size_bytes,
-                    size,
-                    license,
-                );
+                // share of the top versions
+                if total > 0 && !by_ver.is_empty() {
+                    println!("\n{}", "Top versions:".bright_blue());
+                    let top = &by_ver[..by_ver.len().min(5)];
+                    let width = top.iter().map(|v| v.0.len()).max().unwrap_or(0);
+                    let max = top.iter().flat_map(|v| v.1.iter()).cloned().max().unwrap_or(0);
+
+                    for (version, counts) in top {
+                        let sum: u64 = counts.iter().sum();
+                        let share = sum as f64 / total as f64;
+                        let graph = if by_version { sparkline(counts, max) } else { bar(share, 40) };
+                        println!("{} {:>6} {}",
+                            format!("{:w$}", version, w = width).yellow(),
+                            format!("{:.1}%", share * 100.0),
+                            graph.bright_red());
+                    }
+                }
+            },
+        }
+        Ok(())
+    }
+
+
+    /// Show the profile of a user or a team, and all the crates they own,
+    /// sorted by downloads. `kind` is either "user" or "team".
+    pub fn show_owner(&self, kind: &str, name: &str) -> Result<(), String> {
+
+        let profile = match kind {
+            "team" => self.registry().team(name)?,
+            _ => self.registry().user(name)?,
+        };
+        let id = profile["id"].as_u64()
+            .ok_or(format!("couldn't find the {} \"{}\"", kind, name))?;
+
+        let mut crates = self.registry().owned_crates(&format!("{}_id", kind), id)?;
+        crates.sort_by(|a, b| b["downloads"].as_u64().cmp(&a["downloads"].as_u64()));
+        Cache::remember(crates.iter().filter_map(|c| c["name"].as_str()));
+
+        // profile
+        println!("{}\t\t{}", "login:".blue(), profile["login"].as_str().unwrap_or(name).green());
+        if let Some(n) = profile["name"].as_str() {
+            println!("{}\t\t{}", "name:".blue(), n);
+        }
+        println!("{}\t\t{}", "kind:".blue(), kind);
+        if let Some(url) = profile["url"].as_str() {
+            println!("{}\t{}", "profile:".blue(), url.bright_blue().underline());
+        }
+
+        // aggregate totals
+        let downloads: u64 = crates.iter().filter_map(|c| c["downloads"].as_u64()).sum();
+        let recent: u64 = crates.iter().filter_map(|c| c["recent_downloads"].as_u64()).sum();
+        println!("{}\t\t{}", "crates:".blue(), crates.len().to_string().bright_green());
+        println!("{}\t{}", "downloads:".blue(), downloads.to_formatted_string(&Locale::en).red());
+        println!("{}\t\t{}", "recent:".blue(), recent.to_formatted_string(&Locale::en).bright_red());
+
+        if crates.is_empty() {
+            return Ok(());
+        }
+        println!("\n{}", "# crate_name version updated downloads (recent) description".bright_black());
+
+        let counter_len = crates.len().to_string().len();
+        for (i, c) in crates.iter().enumerate() {
+            let recent = match c["recent_downloads"].as_u64() {
+                Some(dl) => format!("({})", dl.to_formatted_string(&Locale::en)).bright_red(),
+                None => "(?)".bright_black(),
+            };
+            println!("{} {} {} {} {} {} {}",
+                format!("{:w$}", i + 1, w = counter_len).bright_black(),
+                c["name"].as_str().unwrap_or("?").green(),
+                c["max_version"].as_str().unwrap_or("?").yellow(),
+                registry::date(&c["updated_at"]).map(|d| date_show(&d)).unwrap_or_default()
+                    .bright_purple(),
+                c["downloads"].as_u64().unwrap_or(0).to_formatted_string(&Locale::en).red(),
+                recent,
+                c["description"].as_str().unwrap_or("").split_whitespace()
+                    .collect::<Vec<_>>().join(" "),
+            );
+        }
+        Ok(())
+    }
+
+
+    /// Audit the licenses of the crates in a list, or the dependencies in a manifest
+    /// or a lockfile, against the `[license]` policy.
+    ///
+    /// Returns whether the policy passes: no license is denied, and there are no
+    /// unknown or missing licenses unless the policy allows them.
+    pub fn license_audit(&self, target: &str, include: &[&str]) -> Result<bool, String> {
+
+        let path = Path::new(target);
+        let mut crates: Vec<(String, Option<String>)> = if path.exists() {
+            manifest::dependencies(path, include)?.into_iter()
+                .map(|d| (d.name, d.version)).collect()
+        } else if let Some(list) = Lists::crates(target) {
+            list.into_iter().map(|c| (c, None)).collect()
+        } else {
+            return Err(format!("\"{}\" is neither a list nor a manifest.", target));
+        };
+        crates.sort();
+        crates.dedup();
+
+        let policy = Config::current().license;
+        let mut passed = true;
+        let mut problems = 0;
+        let mut summary: Vec<(String, usize)> = Vec::new();
+
+        println!("License audit of {} ({} crates):", target.bright_green(), crates.len());
+
+        for (name, version) in crates.iter() {
+            let res = match self.client().get_crate(name) {
+                Ok(res) => res,
+                Err(e) => {
+                    println!("  {} {} couldn't be fetched: {}", "error  ".red(), name.green(), e);
+                    passed = false;
+                    problems += 1;
+                    continue;
+                }
+            };
+            // the exact version, or the newest one matching the requirement, or the latest
+            let v = version.as_ref().and_then(|v| {
+                res.versions.iter().find(|x| &x.num == v).or_else(|| {
+                    let req = VersionReq::parse(v).ok()?;
+                    res.versions.iter()
+                        .find(|x| Version::parse(&x.num).map_or(false, |n| req.matches(&n)))
+                })
+            })
+            .or(res.versions.iter().find(|x| x.num == res.crate_data.max_version))
+            .or(res.versions.get(0));
+            let num = v.map(|v| v.num.as_str()).unwrap_or("?");
+
+            let expr = match v.and_then(|v| v.license.as_ref()).map(|l| l.trim()) {
+                Some(l) if !l.is_empty() => l,
+                _ => {
+                    let label = if policy.allow_missing { "missing".yellow() } else {
+                        passed = false;
+                        "missing".red()
+                    };
+                    println!("  {} {} {} has no license", label, name.green(), num.yellow());
+                    problems += 1;
+                    continue;
+                }
+            };
+            match summary.iter_mut().find(|s| s.0 == expr) {
+                Some(s) => s.1 += 1,
+                None => summary.push((expr.to_string(), 1)),
+            }
+
+            match License::parse(expr) {
+                Ok(license) => match license.check(&policy) {
+                    Verdict::Allowed => (),
+                    Verdict::Denied => {
+                        passed = false;
+                        problems += 1;
+                        println!("  {} {} {} {}", "denied ".red(), name.green(), num.yellow(),
+                            expr.bright_blue().italic());
+                    },
+                    Verdict::Unknown => {
+                        problems += 1;
+                        let label = if policy.allow_unknown { "unknown".yellow() } else {
+                            passed = false;
+                            "unknown".red()
+                        };
+                        println!("  {} {} {} {}", label, name.green(), num.yellow(),
+                            expr.bright_blue().italic());
+                    },
+                },
+                Err(e) => {
+                    passed = false;
+                    problems += 1;
+                    println!("  {} {} {} \"{}\": {}", "invalid".red(), name.green(), num.yellow(),
+                        expr, e);
+                },
+            }
+        }
+        if problems == 0 {
+            println!("  {}", "no problems found".green());
+        }
+
+        // summary of the licenses, the most used first
+        summary.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        println!("\n{}", "Licenses:".bright_blue());
+        let width = summary.iter().map(|s| s.0.len()).max().unwrap_or(0);
+        for (expr, count) in summary.iter() {
+            println!("  {} {}", format!("{:w$}", expr, w = width).bright_blue().italic(),
+                count.to_string().cyan());
+        }
 
+        println!("\n{}", if passed { "The license policy passed.".green() } else {
+            "The license policy failed.".red() });
+        Ok(passed)
+    }
+
+
+    /// Show the health score of a crate, from 0 to 100, with the breakdown
+    /// of each factor scored against the `[health]` thresholds
+    pub fn show_health(&self, crate_name: &str) -> Result<(), String> {
+
+        let res = self.client().get_crate(crate_name).map_err(|e| e.to_string())?;
+        let c = &res.crate_data;
+        Cache::remember(vec![c.name.as_str()]);
+        let owners = self.client().crate_owners(crate_name).map_err(|e| e.to_string())?;
+        let (_, rdeps) = self.registry().reverse_dependencies(crate_name, 1)?;
+
+        let t = Config::current().health;
+        let now = Utc::now();
+        let ratio = |n: f64, of: f64| if of > 0.0 { (n / of).max(0.0).min(1.0) } else { 0.0 };
+
+        // (factor, value, score from 0 to 1, what scores full)
+        let mut factors: Vec<(&str, String, f64, String)> = Vec::new();
+
+        let age = (now - c.updated_at).num_days().max(0) as u64;
+        let stale_width = t.stale_days.saturating_sub(t.fresh_days);
+        let freshness = if stale_width == 0 {
+            if age <= t.fresh_days { 1.0 } else { 0.0 }
+        } else {
+            1.0 - ratio(age.saturating_sub(t.fresh_days) as f64, stale_width as f64)
+        };
+        factors.push(("last update", date_show(&c.updated_at), freshness,
+            format!("within {} days, zero after {}", t.fresh_days, t.stale_days)));
+
+        let releases = res.versions.iter()
+            .filter(|v| !v.yanked && (now - v.created_at).num_days() <= 365).count();
+        factors.push(("release cadence", format!("{} releases in the last year", releases),
+            ratio(releases as f64, t.releases_per_year as f64),
+            format!("{} releases a year", t.releases_per_year)));
+
+        let yanked = res.versions.iter().filter(|v| v.yanked).count();
+        let yanked_ratio = ratio(yanked as f64, res.versions.len() as f64);
+        factors.push(("yanked versions", format!("{} of {}", yanked, res.versions.len()),
+            1.0 - ratio(yanked_ratio, t.max_yanked_ratio),
+            format!("none, zero at {:.0}%", t.max_yanked_ratio * 100.0)));
+
+        factors.push(("owners", owners.len().to_string(),
+            ratio(owners.len() as f64, t.min_owners as f64),
+            format!("{} or more", t.min_owners)));
+
+        let links = [("documentation", &c.documentation), ("repository", &c.repository)];
+        let present: Vec<&str> = links.iter().filter(|l| l.1.is_some()).map(|l| l.0).collect();
+        factors.push(("docs & repository",
+            if present.is_empty() { "none".to_string() } else { present.join(", ") },
+            present.len() as f64 / links.len() as f64,
+            "both".to_string()));
+
+        let recent = c.recent_downloads.unwrap_or(0);
+        factors.push(("recent downloads",
+            format!("{} of {}", recent.to_formatted_string(&Locale::en),
+                c.downloads.to_formatted_string(&Locale::en)),
+            ratio(ratio(recent as f64, c.downloads as f64), t.recent_ratio),
+            format!("{:.0}% of the total", t.recent_ratio * 100.0)));
+
+        // on a logarithmic scale, since a few dependents already mean some trust
+        factors.push(("reverse deps", rdeps.to_formatted_string(&Locale::en),
+            ratio(((rdeps + 1) as f64).ln(), ((t.min_rdeps + 1) as f64).ln()),
+            format!("{} or more", t.min_rdeps)));
+
+        let score = factors.iter().map(|f| f.2).sum::<f64>() / factors.len() as f64 * 100.0;
+        let colorize = |text: String, score: f64| match score as u64 {
+            0..=49 => text.red(),
+            50..=74 => text.yellow(),
+            _ => text.green(),
+        };
+
+        println!("Health of {} {}: {}\n", c.name.green(), c.max_version.yellow(),
+            colorize(format!("{:.0}/100", score), score).bold());
+        println!("{}", "# factor value score (full score)".bright_black());
+
+        let value_len = factors.iter().map(|f| f.1.chars().count()).max().unwrap_or(0);
+        for (factor, value, score, full) in factors.iter() {
+            println!("{} {} {} {} {}",
+                format!("{:17}", factor).blue(),
+                format!("{:w$}", value, w = value_len),
+                colorize(format!("{:3.0}", score * 100.0), score * 100.0),
+                format!("{:10}", bar(*score, 10)).bright_black(),
+                format!("({})", full).bright_black());
+        }
+        Ok(())
+    }
+
+
+    /// Show the crates of a list with new releases since the last check, or since a date,
+    /// and remember their latest versions for the next check. Looking back since a date
+    /// doesn't change what the next check compares with.
+    ///
+    /// Returns whether there are new releases.
+    pub fn watch_list(&self, list: &str, since: Option<DateTime<Utc>>) -> Result<bool, String> {
+
+        let crates = Lists::crates(list).ok_or(format!("List \"{}\" doesn't exist.", list))?;
+        let previous = Watch::seen(list);
+        let mut seen = Seen::new();
+        let mut updated = 0;
+
+        for name in crates.iter() {
+            let res = match self.client().get_crate(name) {
+                Ok(res) => res,
+                Err(e) => {
+                    println!("Error: couldn't fetch the crate \"{}\": {}", name.red(), e);
+                    // keep what was seen, for the next check
+                    if let Some(s) = previous.get(name) { seen.insert(name.clone(), s.clone()); }
+                    continue;
+                }
+            };
+            let c = &res.crate_data;
+            seen.insert(name.clone(), (c.max_version.clone(), c.updated_at));
+
+            let last_seen = previous.get(name);
+            let threshold = match (since, last_seen) {
+                (Some(since), _) => since,
+                (None, Some((_, at))) => *at,
+                (None, None) => {
+                    println!("{} {} {}", "watching".bright_black(), c.name.green(),
+                        c.max_version.yellow());
+                    continue;
+                }
+            };
+
+            // the releases since the threshold, oldest first
+            let mut releases: Vec<&crates_io_api::Version> = res.versions.iter()
+                .filter(|v| !v.yanked && v.created_at > threshold)
+                .filter(|v| since.is_some() || last_seen.map_or(true, |s| s.0 != v.num))
+                .collect();
+            if releases.is_empty() {
+                continue;
+            }
+            releases.sort_by_key(|v| v.created_at);
+            updated += 1;
+
+            // the version before the releases
+            let from = match (since, last_seen) {
+                (None, Some((version, _))) => Some(version.clone()),
+                _ => res.versions.iter().filter(|v| !v.yanked && v.created_at <= threshold)
+                    .max_by_key(|v| v.created_at).map(|v| v.num.clone()),
+            };
+            let to = releases.last().unwrap();
+
+            let jump = match (from.as_ref().and_then(|f| Version::parse(f).ok()),
+                Version::parse(&to.num).ok()) {
+                (Some(a), Some(b)) if version_series(&a) != version_series(&b) => "breaking".red(),
+                (Some(a), Some(b)) if a.minor != b.minor => "minor".yellow(),
+                (Some(_), Some(_)) => "patch".green(),
+                _ => "new".cyan(),
+            };
+            println!("{} {} → {} {} {}",
+                c.name.green(),
+                from.unwrap_or("?".to_string()).yellow(),
+                to.num.bright_yellow(),
+                jump,
+                date_show(&to.created_at).purple());
+            if releases.len() > 1 {
+                let nums: Vec<&str> = releases.iter().map(|v| v.num.as_str()).collect();
+                println!("\t{} releases: {}", releases.len(), nums.join(", ").bright_black());
             }
         }
+
+        match since {
+            Some(since) => println!("\n{} of {} crates in the list \"{}\" have new releases since {}.",
+                updated, crates.len(), list.bright_green(), date_str(&since)),
+            None => println!("\n{} of {} crates in the list \"{}\" have new releases since the last check.",
+                updated, crates.len(), list.bright_green()),
+        }
+
+        if since.is_none() {
+            Watch::save(list, &seen).map_err(|e| format!("couldn't save the watch state to {}: {}",
+                Watch::file().display(), e))?;
+        }
+        Ok(updated > 0)
+    }
+
+
+    /// Generate an Atom or RSS feed of the latest versions of the crates in a list,
+    /// or of the new crates when `source` is "new-crates"
+    pub fn feed(&self, source: &str, format: &str, versions: usize) -> Result<String, String> {
+
+        let registry = Config::current().registry;
+        let summary_of = |description: &Option<String>, license: Option<&String>| {
+            let mut text = description.as_ref()
+                .map(|d| d.split_whitespace().collect::<Vec<_>>().join(" "))
+                .unwrap_or_default();
+            if let Some(l) = license {
+                text = format!("{} (license: {})", text, l).trim().to_string();
+            }
+            if text.is_empty() { None } else { Some(text) }
+        };
+        // a stable id for each feed, since their links may be the same
+        let host = registry.split("://").last().unwrap_or("").split('/').next().unwrap_or("");
+        let tag = |specific: &str| format!("tag:{},2019:{}:{}", host, APPNAME, specific);
+        let mut entries = Vec::new();
+
+        let (title, link, id) = if source == "new-crates" {
+            let summary = self.client().summary().map_err(|e| e.to_string())?;
+            for c in summary.new_crates.iter() {
+                entries.push(Entry {
+                    title: format!("{} {}", c.name, c.max_version),
+                    link: format!("{}/crates/{}", registry, c.name),
+                    date: c.created_at,
+                    summary: summary_of(&c.description, c.license.as_ref()),
+                });
+            }
+            ("New crates".to_string(), format!("{}/crates?sort=new", registry), tag("new-crates"))
+
+        } else {
+            let crates = Lists::crates(source)
+                .ok_or(format!("List \"{}\" doesn't exist.", source))?;
+            for name in crates.iter() {
+                // stderr, since the feed may be written to stdout
+                let res = match self.client().get_crate(name) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        eprintln!("Warning: skipping the crate \"{}\", \
+                            which couldn't be fetched: {}", name, e);
+                        continue;
+                    }
+                };
+                let mut latest: Vec<&crates_io_api::Version> = res.versions.iter()
+                    .filter(|v| !v.yanked).collect();
+                latest.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+                for v in latest.iter().take(versions) {
+                    entries.push(Entry {
+                        title: format!("{} {}", res.crate_data.name, v.num),
+                        link: format!("{}/crates/{}/{}", registry, res.crate_data.name, v.num),
+                        date: v.created_at,
+                        summary: summary_of(&res.crate_data.description, v.license.as_ref()),
+                    });
+                }
+            }
+            let list: String = source.bytes().map(|b| match b {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => {
+                    (b as char).to_string()
+                },
+                _ => format!("%{:02X}", b),
+            }).collect();
+            (format!("Crate list \"{}\"", source), registry.clone(),
+                tag(&format!("list:{}", list)))
+        };
+
+        Ok(match format {
+            "rss" => feed::rss(&title, &link, entries),
+            _ => feed::atom(&title, &link, &id, entries),
+        })
+    }
+
+
+    /// Fetch the README of a crate as rendered to HTML by the registry,
+    /// of its latest version unless another one is given
+    pub fn readme(&self, crate_name: &str, version: Option<&str>) -> Result<String, String> {
+
+        let version = match version {
+            Some(v) => v.to_string(),
+            None => self.client().get_crate(crate_name).map_err(|e| e.to_string())?
+                .crate_data.max_version,
+        };
+        let readme = self.registry().get_text(&format!("crates/{}/{}/readme", crate_name, version))
+            .map_err(|e| format!("couldn't fetch the README of {} {}: {}", crate_name, version, e))?;
+        Cache::remember(vec![crate_name]);
+        Ok(readme)
+    }
+
+
+    /// Export a list as JSON, plain text or a Cargo.toml `[dependencies]` block
+    pub fn export_list(&self, list: &str, format: &str) -> Result<(), Error> {
+
+        let crates = match Lists::crates(list) {
+            Some(crates) => crates,
+            None => {
+                println!("List \"{}\" doesn't exist.", list.red());
+                return Ok(());
+            }
+        };
+
+        match format {
+            "json" => {
+                let json = serde_json::json!({ "name": list, "crates": crates });
+                println!("{}", serde_json::to_string_pretty(&json)
+                    .expect("Error: couldn't serialize the list."));
+            },
+            "cargo" => {
+                println!("[dependencies]");
+                for c in crates.iter() {
+                    match self.client().get_crate(c) {
+                        Ok(res) => println!("{} = \"{}\"", c, res.crate_data.max_version),
+                        Err(_) => println!("# {} = \"?\" # couldn't fetch the latest version", c),
+                    }
+                }
+            },
+            _ => for c in crates.iter() { println!("{}", c); },
+        }
         Ok(())
     }
 
+
+    /// Sort crate names by name, downloads or last update,
+    /// fetching the registry data when needed
+    pub fn sort_crates(&self, crates: &mut Vec<String>, by: &str) {
+        match by {
+            "downloads" | "updated" => {
+                // crates that can't be fetched are sorted last
+                let mut keyed: Vec<(String, u64, i64)> = crates.drain(..).map(|name| {
+                    match self.client().get_crate(&name) {
+                        Ok(res) => {
+                            let (downloads, updated) = (res.crate_data.downloads,
+                                res.crate_data.updated_at.timestamp());
+                            (name, downloads, updated)
+                        },
+                        Err(_) => (name, 0, i64::min_value()),
+                    }
+                }).collect();
+
+                if by == "downloads" {
+                    keyed.sort_by(|a, b| b.1.cmp(&a.1));
+                } else {
+                    keyed.sort_by(|a, b| b.2.cmp(&a.2));
+                }
+                crates.extend(keyed.into_iter().map(|k| k.0));
+            },
+            _ => crates.sort(),
+        }
+    }
+
 }
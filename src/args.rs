@@ -1,10 +1,23 @@
 use super::{VERSION, APPNAME};
 
+use std::fs;
+use std::io;
+use std::process;
+use std::path::{Path, PathBuf};
+
 use clap::{ArgMatches, Arg, App, AppSettings, SubCommand};
 use colored::*;
 
-use crate::conf::Lists;
+use crate::conf::{Config, Doctor, Lists, Output, Settings};
 use crate::actions::Actuator;
+use crate::cache::Cache;
+use crate::tui::Tui;
+use crate::picker;
+use crate::readme;
+use crate::util::{page, parse_date, pattern_match, set_date_display, DateMode};
+
+mod completions;
+mod manual;
 
 pub struct CliArguments<'a> {
     matches: ArgMatches<'a>
@@ -18,15 +31,69 @@ impl<'a> CliArguments<'a> {
         }
     }
 
-    /// Defines the CLI arguments
+    /// Returns the configuration file provided with `--config` or `CRIN_CONFIG`
+    pub fn config(&self) -> Option<PathBuf> {
+        self.global_value("config").map(PathBuf::from)
+    }
+
+    /// Returns the value of a global argument,
+    /// which may appear after any of the subcommands
+    fn global_value(&self, name: &str) -> Option<&str> {
+        let mut matches = &self.matches;
+        let mut value = matches.value_of(name);
+        while let (_, Some(sub)) = matches.subcommand() {
+            matches = sub;
+            value = matches.value_of(name).or(value);
+        }
+        value
+    }
+
+    /// Returns true if a global flag is present after any of the subcommands
+    fn global_flag(&self, name: &str) -> bool {
+        let mut matches = &self.matches;
+        let mut present = matches.is_present(name);
+        while let (_, Some(sub)) = matches.subcommand() {
+            matches = sub;
+            present |= matches.is_present(name);
+        }
+        present
+    }
+
+    /// Parses the CLI arguments
     fn set_args() -> ArgMatches<'a> {
+        Self::app().get_matches()
+    }
+
+    /// Defines the CLI arguments
+    pub fn app() -> App<'a, 'a> {
 
         App::new(APPNAME)
             .version(VERSION)
+            .about(env!("CARGO_PKG_DESCRIPTION"))
             .setting(AppSettings::VersionlessSubcommands)
             .setting(AppSettings::UnifiedHelpMessage)
 
             // global flags
+            .arg(Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .value_name("path")
+                .env("CRIN_CONFIG")
+                .help("use this configuration file instead of the default one")
+                .global(true)
+            )
+            .arg(Arg::with_name("dates")
+                .long("dates")
+                .takes_value(true)
+                .possible_values(&["absolute", "relative", "both"])
+                .help("how to show the dates (default: both)")
+                .global(true)
+            )
+            .arg(Arg::with_name("utc")
+                .long("utc")
+                .help("show the dates in UTC instead of local time")
+                .global(true)
+            )
             // TODO: add flag for no colors
             /*
             .arg(Arg::with_name("page")
@@ -70,17 +137,26 @@ impl<'a> CliArguments<'a> {
                      .required(false)
                      .multiple(true)
                 )
+                .arg(Arg::with_name("owners")
+                     .long("owners")
+                     .help("fetch the user and the team owners separately, with all their details")
+                )
             )
 
             // search
             .subcommand(SubCommand::with_name("search")
-                .about("Search for crates. Shows 1 page of 100 results")
+                .about("Search for crates. Shows 1 page of results (100 by default)")
                 .arg(Arg::with_name("query")
                     .help("the search query")
                     //.index(1)
                     .required(false)
                     .empty_values(false)
                 )
+                .arg(Arg::with_name("interactive")
+                     .short("i")
+                     .long("interactive")
+                     .help("browse the results in the full-screen interface (same as `crin tui`)")
+                )
                 /*
                 .arg(Arg::with_name("category")
                      .short("c")
@@ -103,6 +179,231 @@ impl<'a> CliArguments<'a> {
                 */
             )
 
+            // compare
+            .subcommand(SubCommand::with_name("compare")
+                .about("Compare several crates side by side")
+                .arg(Arg::with_name("crates")
+                    .help("the crates to compare")
+                    .required(true)
+                    .multiple(true)
+                    .min_values(2)
+                )
+            )
+
+            // downloads
+            .subcommand(SubCommand::with_name("downloads")
+                .about("Show the daily downloads of a crate, with sparklines")
+                .arg(Arg::with_name("crate_name")
+                    .help("the name of the crate")
+                    .required(true)
+                )
+                .arg(Arg::with_name("days")
+                    .long("days")
+                    .help("the number of days to show, up to 90")
+                    .takes_value(true)
+                    .default_value("90")
+                    .validator(|d| match d.parse::<i64>() {
+                        Ok(1..=90) => Ok(()),
+                        _ => Err("must be a number between 1 and 90".to_string()),
+                    })
+                )
+                .arg(Arg::with_name("by-version")
+                    .long("by-version")
+                    .help("show the daily downloads of each of the top versions")
+                )
+                .arg(Arg::with_name("format")
+                    .short("f")
+                    .long("format")
+                    .help("the output format (default: text, or json if the output setting is json)")
+                    .takes_value(true)
+                    .possible_values(&["text", "csv", "json"])
+                )
+            )
+
+            // reverse dependencies
+            .subcommand(SubCommand::with_name("rdeps")
+                .about("Browse the reverse dependencies of a crate")
+                .arg(Arg::with_name("crate_name")
+                    .help("the name of the crate")
+                    .required(true)
+                )
+                .arg(Arg::with_name("sort")
+                    .long("sort")
+                    .help("sort the dependents (by name or update fetches all of them)")
+                    .takes_value(true)
+                    .possible_values(&["downloads", "name", "updated"])
+                    .default_value("downloads")
+                )
+                .arg(Arg::with_name("optional-only")
+                    .long("optional-only")
+                    .help("only the dependents where the dependency is optional")
+                )
+                .arg(Arg::with_name("req")
+                    .long("req")
+                    .help("only the dependents on a version matching this requirement, e.g. ^1")
+                    .takes_value(true)
+                    .value_name("semver")
+                )
+                .arg(Arg::with_name("limit")
+                    .long("limit")
+                    .help("the number of dependents per page, or 0 for all of them")
+                    .takes_value(true)
+                    .default_value("100")
+                    .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                )
+                .arg(Arg::with_name("page")
+                    .long("page")
+                    .help("the page of dependents to show")
+                    .takes_value(true)
+                    .default_value("1")
+                    .validator(|n| match n.parse::<usize>() {
+                        Ok(n) if n > 0 => Ok(()),
+                        _ => Err("must be a number greater than 0".to_string()),
+                    })
+                )
+            )
+
+            // impact
+            .subcommand(SubCommand::with_name("impact")
+                .about("Show which series of a crate its reverse dependencies require, by downloads")
+                .after_help("Each dependent is counted in the compatible series (e.g. 1.x, 0.9.x) \
+                    of the newest published version that satisfies its requirement, \
+                    so that you can see who is still on an old major before yanking or \
+                    deprecating it. It fetches all the reverse dependencies.")
+                .arg(Arg::with_name("crate_name")
+                    .help("the name of the crate")
+                    .required(true)
+                )
+            )
+
+            // license audit
+            .subcommand(SubCommand::with_name("license-audit")
+                .about("Check the licenses of a list or a project's dependencies against your policy")
+                .after_help("The policy is set in the [license] table of the configuration:\n\n    \
+                    [license]\n    \
+                    allow = [\"MIT\", \"Apache-2.0\", \"BSD-3-Clause\"]  # empty allows all\n    \
+                    deny = [\"GPL-3.0\"]\n    \
+                    allow_unknown = false  # pass licenses neither allowed nor denied\n    \
+                    allow_missing = false  # pass crates without a license\n\n\
+                    It exits with 1 when the policy fails, and with 2 on errors.")
+                .arg(Arg::with_name("target")
+                    .help("a list, or a Cargo.toml, a Cargo.lock or a project directory")
+                    .required(true)
+                )
+                .arg(Arg::with_name("include")
+                    .long("include")
+                    .help("also include these kinds of manifest dependencies")
+                    .takes_value(true)
+                    .use_delimiter(true)
+                    .multiple(true)
+                    .possible_values(&["dev", "build"])
+                )
+            )
+
+            // health
+            .subcommand(SubCommand::with_name("health")
+                .about("Score the health of a crate, with the breakdown of each factor")
+                .after_help("Each factor scores from 0 to 100, and the health is their average. \
+                    The thresholds can be changed in the [health] table of the configuration.")
+                .arg(Arg::with_name("crate_name")
+                    .help("the name of the crate")
+                    .required(true)
+                )
+            )
+
+            // feed
+            .subcommand(SubCommand::with_name("feed")
+                .about("Generate an Atom or RSS feed of the releases in a list, or of the new crates")
+                .after_help("The feed only depends on the release data, so that regenerating it \
+                    without new releases produces the same file.\n\n\
+                    EXAMPLE:\n    crin feed devlist -o ~/feeds/devlist.xml")
+                .arg(Arg::with_name("source")
+                    .help("a list, or new-crates")
+                    .required(true)
+                )
+                .arg(Arg::with_name("output")
+                    .short("o")
+                    .long("output")
+                    .help("the file to write (default: the standard output)")
+                    .takes_value(true)
+                    .value_name("file")
+                )
+                .arg(Arg::with_name("format")
+                    .short("f")
+                    .long("format")
+                    .help("the feed format")
+                    .takes_value(true)
+                    .possible_values(&["atom", "rss"])
+                    .default_value("atom")
+                )
+                .arg(Arg::with_name("versions")
+                    .long("versions")
+                    .help("the latest versions of each crate in the list to include")
+                    .takes_value(true)
+                    .default_value("5")
+                    .validator(|n| n.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                )
+            )
+
+            // readme
+            .subcommand(SubCommand::with_name("readme")
+                .about("Show the README of a crate in the terminal")
+                .after_help("The README is shown through $PAGER (default: less -R) \
+                    when it doesn't fit in the terminal.\n\n\
+                    EXAMPLES:\n    crin readme serde\n    crin readme serde@1.0.0 --raw")
+                .arg(Arg::with_name("crate")
+                    .help("the name of the crate, optionally with a version, e.g. serde@1.0.0")
+                    .required_unless("file")
+                )
+                .arg(Arg::with_name("raw")
+                    .long("raw")
+                    .help("print the HTML as received, without rendering it")
+                )
+                .arg(Arg::with_name("file")
+                    .long("file")
+                    .help("render a local HTML file instead of fetching a README")
+                    .takes_value(true)
+                    .value_name("path")
+                    .conflicts_with("crate")
+                )
+                .arg(Arg::with_name("no-pager")
+                    .long("no-pager")
+                    .help("print the README directly, without a pager")
+                )
+            )
+
+            // user & team
+            .subcommand(SubCommand::with_name("user")
+                .about("Show a user's profile and all the crates they own")
+                .arg(Arg::with_name("login")
+                    .help("the login of the user, e.g. joseluis")
+                    .required(true)
+                )
+            )
+            .subcommand(SubCommand::with_name("team")
+                .about("Show a team's profile and all the crates it owns")
+                .arg(Arg::with_name("name")
+                    .help("the name of the team, e.g. github:rust-lang:libs")
+                    .required(true)
+                )
+            )
+
+            // tui
+            .subcommand(SubCommand::with_name("tui")
+                .about("Browse the search results interactively, in full screen")
+                .after_help("KEYS:\n    \
+                    type         edit the query, which is searched when you pause\n    \
+                    ↑ ↓ PgUp PgDn  select a crate\n    \
+                    Enter        fetch the complete details of the crate\n    \
+                    Ctrl+A       add the crate to a list (Tab completes the list names)\n    \
+                    Ctrl+O       open the repository\n    \
+                    Ctrl+D       open the documentation\n    \
+                    Esc          quit")
+                .arg(Arg::with_name("query")
+                    .help("the initial search query")
+                    .required(false)
+                )
+            )
 
             .subcommand(SubCommand::with_name("list")
                 .about("Manage your lists of crates")
@@ -111,6 +412,7 @@ impl<'a> CliArguments<'a> {
                 .subcommand(SubCommand::with_name("show")
                     .about("shows the crates contained in the list")
                     .arg(Arg::with_name("list")
+                         .help("the list to show (default: pick it interactively, or show all the lists)")
                          .required(false)
                          .empty_values(false)
                     )
@@ -122,18 +424,30 @@ impl<'a> CliArguments<'a> {
                          .multiple(true)
                          .empty_values(true)
                      )
+                    .arg(Arg::with_name("filter")
+                         .long("filter")
+                         .help("only show the crates matching a substring or a glob")
+                         .takes_value(true)
+                         .value_name("pattern")
+                    )
+                    .arg(Arg::with_name("sort")
+                         .long("sort")
+                         .help("sort the crates shown")
+                         .takes_value(true)
+                         .possible_values(&["name", "downloads", "updated"])
+                    )
                 )
                 .subcommand(SubCommand::with_name("add")
                     .about("add a crate to a list")
                     .arg(Arg::with_name("list")
-                         .help("the list where to add the crate")
-                         .required(true)
+                         .help("the list where to add the crate (default: pick it interactively)")
+                         .required(false)
                          .empty_values(false)
                          .index(1)
                     )
                     .arg(Arg::with_name("crate")
-                         .help("the crate to add to the list")
-                         .required(true)
+                         .help("the crate to add to the list (default: pick it interactively)")
+                         .required(false)
                          .empty_values(false)
                          //.multiple(true) // TODO: allow multiple
                          .index(2)
@@ -152,28 +466,165 @@ impl<'a> CliArguments<'a> {
                     .visible_alias("delete")
 
                     .arg(Arg::with_name("list")
-                         .help("The list to delete (must be empty)")
+                         .help("The list to delete (must be empty unless forced)")
                          .required(true)
                          .empty_values(false)
                          //.multiple(true) // TODO: allow multiple
                     )
+                    .arg(Arg::with_name("force")
+                         .short("f")
+                         .long("force")
+                         .help("delete the list even if it's not empty")
+                    )
+                    .arg(Arg::with_name("yes")
+                         .short("y")
+                         .long("yes")
+                         .help("don't ask for confirmation")
+                         .requires("force")
+                    )
+                )
+                .subcommand(SubCommand::with_name("rename")
+                    .about("rename a list")
+                    .arg(Arg::with_name("old")
+                         .help("the list to rename")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("new")
+                         .help("the new name of the list (must not exist)")
+                         .required(true)
+                         .empty_values(false)
+                    )
                 )
                 .subcommand(SubCommand::with_name("rem")
                     .about("remove a crate from a list")
                     .visible_alias("remove")
 
                     .arg(Arg::with_name("list")
-                         .help("The list containing the crate")
-                         .required(true)
+                         .help("The list containing the crate (default: pick it interactively)")
+                         .required(false)
                          .empty_values(false)
                     )
                     .arg(Arg::with_name("crate")
-                         .help("the crate to remove")
-                         .required(true)
+                         .help("the crate to remove (default: pick it interactively)")
+                         .required(false)
                          .empty_values(false)
                          //.multiple(true) // TODO: allow multiple
                     )
                 )
+                .subcommand(SubCommand::with_name("sort")
+                    .about("sort the crates in a list")
+                    .arg(Arg::with_name("list")
+                         .help("the list to sort")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("by")
+                         .long("by")
+                         .help("the sorting criteria")
+                         .takes_value(true)
+                         .possible_values(&["name", "downloads", "updated"])
+                         .default_value("name")
+                    )
+                )
+                .subcommand(SubCommand::with_name("dedupe")
+                    .about("remove the duplicated crates in a list (or in all of them)")
+                    .arg(Arg::with_name("list")
+                         .help("the list to de-duplicate (default: all)")
+                         .required(false)
+                         .empty_values(false)
+                    )
+                )
+                .subcommand(SubCommand::with_name("export")
+                    .about("export a list as json, plain text or a Cargo.toml dependencies block")
+                    .arg(Arg::with_name("list")
+                         .help("the list to export")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("format")
+                         .short("f")
+                         .long("format")
+                         .help("the output format (default: json if the output setting is json, \
+                            else txt)")
+                         .takes_value(true)
+                         .possible_values(&["json", "txt", "cargo"])
+                    )
+                )
+                .subcommand(SubCommand::with_name("import")
+                    .about("import a list from a json, plain text or Cargo.toml file")
+                    .arg(Arg::with_name("file")
+                         .help("the file to import")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("into")
+                         .long("into")
+                         .help("the list where to import the crates (default: the file name)")
+                         .takes_value(true)
+                         .value_name("list")
+                    )
+                    .arg(Arg::with_name("format")
+                         .short("f")
+                         .long("format")
+                         .help("the input format (default: guessed from a .json, .txt or .toml extension)")
+                         .takes_value(true)
+                         .possible_values(&["json", "txt", "cargo"])
+                    )
+                    .arg(Arg::with_name("merge")
+                         .long("merge")
+                         .help("add the crates to the existing ones (default)")
+                         .conflicts_with("replace")
+                    )
+                    .arg(Arg::with_name("replace")
+                         .long("replace")
+                         .help("replace the existing crates in the list")
+                    )
+                )
+                .subcommand(SubCommand::with_name("watch")
+                    .about("show the crates of a list with new releases since the last check")
+                    .after_help("The first check only remembers the latest versions. \
+                        It exits with 1 when there are new releases, and with 2 on errors, \
+                        so that it can be used from cron jobs.")
+                    .arg(Arg::with_name("list")
+                         .help("the list to watch")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("since")
+                         .long("since")
+                         .help("show the releases since this date (YYYY-MM-DD or RFC 3339) instead, \
+                             without updating the last check")
+                         .takes_value(true)
+                         .value_name("date")
+                         .validator(|d| parse_date(&d).map(|_| ())
+                            .ok_or("expected a date like 2019-04-21".to_string()))
+                    )
+                )
+                .subcommand(SubCommand::with_name("from-manifest")
+                    .about("create a list from the dependencies of a Cargo.toml or Cargo.lock")
+                    .arg(Arg::with_name("path")
+                         .help("the manifest, the lockfile or the project directory")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("name")
+                         .short("n")
+                         .long("name")
+                         .help("the name of the new list")
+                         .takes_value(true)
+                         .value_name("list")
+                         .required(true)
+                    )
+                    .arg(Arg::with_name("include")
+                         .long("include")
+                         .help("also include these kinds of manifest dependencies")
+                         .takes_value(true)
+                         .use_delimiter(true)
+                         .multiple(true)
+                         .possible_values(&["dev", "build"])
+                    )
+                )
 
                 /*
                 .subcommand(SubCommand::with_name("copy")
@@ -253,8 +704,102 @@ impl<'a> CliArguments<'a> {
                 */
                 //
             )
-            .get_matches()
+
+            .subcommand(SubCommand::with_name("config")
+                .about("Manage the configuration file")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+
+                .subcommand(SubCommand::with_name("path")
+                    .about("show the path to the configuration file (and the project-local one)")
+                )
+                .subcommand(SubCommand::with_name("get")
+                    .about("show the value of a setting")
+                    .arg(Arg::with_name("key")
+                         .help("the dotted path to the setting (e.g. lists.devlist)")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                )
+                .subcommand(SubCommand::with_name("set")
+                    .about("change the value of a setting")
+                    .arg(Arg::with_name("key")
+                         .help("the dotted path to the setting")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                    .arg(Arg::with_name("value")
+                         .help("the new value, parsed as TOML or else stored as a string")
+                         .required(true)
+                    )
+                )
+                .subcommand(SubCommand::with_name("unset")
+                    .about("remove a setting")
+                    .arg(Arg::with_name("key")
+                         .help("the dotted path to the setting")
+                         .required(true)
+                         .empty_values(false)
+                    )
+                )
+                .subcommand(SubCommand::with_name("edit")
+                    .about("open the configuration file in your $EDITOR")
+                )
+                .subcommand(SubCommand::with_name("doctor")
+                    .about("check the configuration for problems")
+                    .arg(Arg::with_name("fix")
+                         .long("fix")
+                         .help("repair the problems found, after backing up the file")
+                    )
+                )
+            )
+
+            // completions
+            .subcommand(SubCommand::with_name("completions")
+                .about("Generate a shell completion script")
+                .after_help("The bash, zsh and fish scripts also complete the names of your \
+                    lists, and the names of the crates you have seen recently.\n\n\
+                    EXAMPLE:\n    crin completions bash > ~/.local/share/bash-completion/completions/crin")
+                .arg(Arg::with_name("shell")
+                    .help("the shell to generate the script for")
+                    .required(true)
+                    .possible_values(&["bash", "zsh", "fish", "powershell", "elvish"])
+                )
+            )
+            // manual
+            .subcommand(SubCommand::with_name("man")
+                .about("Generate the man page or the Markdown command reference")
+                .after_help("EXAMPLE:\n    crin man > ~/.local/share/man/man1/crin.1\n    \
+                    crin man --format markdown > COMMANDS.md")
+                .arg(Arg::with_name("format")
+                    .short("f")
+                    .long("format")
+                    .help("the output format")
+                    .takes_value(true)
+                    .possible_values(&["roff", "markdown"])
+                    .default_value("roff")
+                )
+            )
+            // used by the completion scripts
+            .subcommand(SubCommand::with_name("complete")
+                .setting(AppSettings::Hidden)
+                .arg(Arg::with_name("what")
+                    .required(true)
+                    .possible_values(&["lists", "crates"])
+                )
+                .arg(Arg::with_name("list")
+                    .long("list")
+                    .takes_value(true)
+                    .help("only the crates in this list")
+                )
+            )
+    }
+
+
+    /// Runs the full-screen interface, which needs an interactive terminal
+    fn tui(query: Option<&str>) {
+        if let Err(e) = Tui::new(query).run() {
+            println!("Error: couldn't run the interactive interface: {}", e);
         }
+    }
 
 
     /// Parses the received CLI arguments and triggers the appropriate actions
@@ -262,17 +807,30 @@ impl<'a> CliArguments<'a> {
 
         let act = Actuator::new();
 
+        set_date_display(
+            match self.global_value("dates") {
+                Some("absolute") => DateMode::Absolute,
+                Some("relative") => DateMode::Relative,
+                _ => DateMode::Both,
+            },
+            self.global_flag("utc"),
+            &Config::current().date_format);
+
         match self.matches.subcommand() {
 
             ("show", Some(crate_name)) => {
                 let _ = act.show_crate(
                     crate_name.value_of("crate_name").unwrap(),
-                    crate_name.occurrences_of("reverse"));
+                    crate_name.occurrences_of("reverse"),
+                    crate_name.is_present("owners"));
                 },
 
             // TODO: improve usage of default values
             // TODO: allow passing page & page_num
             // TODO: add filters
+            ("search", Some(query)) if query.is_present("interactive") => {
+                Self::tui(query.value_of("query"));
+            },
             ("search", Some(query)) => {
                 let _ = act.search_crate(query.value_of("query"),
                     if let Some(p) = self.matches.value_of("page") {
@@ -280,12 +838,97 @@ impl<'a> CliArguments<'a> {
                         p.parse::<u64>().unwrap_or(1)
                         } else {1},
                     if let Some(pp) = self.matches.value_of("per_page") {
-                        // If the number is not recognized, defaults to 100 per page
-                        pp.parse::<u64>().unwrap_or(100)
-                        } else {100}
+                        // If the number is not recognized, defaults to the configured one
+                        pp.parse::<u64>().unwrap_or(Config::current().per_page)
+                        } else {Config::current().per_page}
                     );
                 },
 
+            ("tui", Some(args)) => Self::tui(args.value_of("query")),
+
+            ("downloads", Some(args)) => {
+                let format = args.value_of("format").unwrap_or(Config::current().output.as_str());
+                let _ = act.show_downloads(args.value_of("crate_name").unwrap(),
+                    args.value_of("days").unwrap().parse().unwrap_or(90),
+                    args.is_present("by-version"), format);
+            },
+            ("rdeps", Some(args)) => {
+                if let Err(e) = act.show_rdeps(args.value_of("crate_name").unwrap(),
+                    args.value_of("sort").unwrap(), args.is_present("optional-only"),
+                    args.value_of("req"), args.value_of("limit").unwrap().parse().unwrap_or(100),
+                    args.value_of("page").unwrap().parse().unwrap_or(1)) {
+                    println!("Error: {}", e);
+                }
+            },
+            ("impact", Some(args)) => {
+                if let Err(e) = act.show_impact(args.value_of("crate_name").unwrap()) {
+                    println!("Error: {}", e);
+                }
+            },
+            ("license-audit", Some(args)) => {
+                let include: Vec<&str> = args.values_of("include")
+                    .map(|v| v.collect()).unwrap_or_default();
+                match act.license_audit(args.value_of("target").unwrap(), &include) {
+                    Ok(true) => (),
+                    Ok(false) => process::exit(1),
+                    Err(e) => {
+                        println!("Error: {}", e);
+                        process::exit(2);
+                    }
+                }
+            },
+            ("health", Some(args)) => {
+                if let Err(e) = act.show_health(args.value_of("crate_name").unwrap()) {
+                    println!("Error: {}", e);
+                }
+            },
+            ("feed", Some(args)) => {
+                let feed = act.feed(args.value_of("source").unwrap(),
+                    args.value_of("format").unwrap(),
+                    args.value_of("versions").unwrap().parse().unwrap_or(5));
+                match (feed, args.value_of("output")) {
+                    (Ok(xml), Some(file)) => if let Err(e) = fs::write(file, xml) {
+                        println!("Error: couldn't write the feed to {}: {}", file, e);
+                    },
+                    (Ok(xml), None) => print!("{}", xml),
+                    (Err(e), _) => println!("Error: {}", e),
+                }
+            },
+            ("readme", Some(args)) => {
+                let html = match (args.value_of("file"), args.value_of("crate")) {
+                    (Some(file), _) => fs::read_to_string(file)
+                        .map_err(|e| format!("couldn't read {}: {}", file, e)),
+                    (None, Some(c)) => {
+                        let mut parts = c.splitn(2, '@');
+                        act.readme(parts.next().unwrap_or(c), parts.next())
+                    },
+                    (None, None) => unreachable!(),
+                };
+                let text = match html {
+                    Ok(html) if args.is_present("raw") => html,
+                    Ok(html) => readme::render(&html),
+                    Err(e) => {
+                        println!("Error: {}", e);
+                        process::exit(2);
+                    }
+                };
+                if args.is_present("no-pager") { print!("{}", text); } else { page(&text); }
+            },
+            ("user", Some(args)) => {
+                if let Err(e) = act.show_owner("user", args.value_of("login").unwrap()) {
+                    println!("Error: {}", e);
+                }
+            },
+            ("team", Some(args)) => {
+                if let Err(e) = act.show_owner("team", args.value_of("name").unwrap()) {
+                    println!("Error: {}", e);
+                }
+            },
+            ("compare", Some(args)) => {
+                let crates: Vec<&str> = args.values_of("crates").unwrap().collect();
+                let _ = act.compare_crates(&crates);
+            },
+
             ("summary", Some(summary_matches)) => {
                     match summary_matches.subcommand() {
                         ("new", Some(_)) => { let _ = act.show_summary_new_crates(); }
@@ -304,23 +947,38 @@ impl<'a> CliArguments<'a> {
             ("list", Some(list_matches)) => {
                 match &list_matches.subcommand() {
                     ("show", Some(args)) => {
-                        if let Some(list) = args.value_of("list") {
+                        let list = args.value_of("list").map(String::from)
+                            .or_else(|| picker::pick("list:", &Lists::names(), false));
+
+                        if let Some(list) = list.as_ref().map(String::as_str) {
                             if Lists::exists(list) {
-                                println!("Your list \"{}\" contains {} crates:",
-                                    list.bright_green(), Lists::quantity(list));
+                                let mut crates = Lists::crates(list).unwrap_or_default();
+                                let total = crates.len();
+
+                                if let Some(pattern) = args.value_of("filter") {
+                                    crates.retain(|c| pattern_match(pattern, c));
+                                    println!("{} of {} crates in your list \"{}\" match \"{}\":",
+                                        crates.len(), total, list.bright_green(), pattern.cyan());
+                                } else {
+                                    println!("Your list \"{}\" contains {} crates:",
+                                        list.bright_green(), total);
+                                }
+                                if let Some(by) = args.value_of("sort") {
+                                    act.sort_crates(&mut crates, by);
+                                }
 
                                 match args.occurrences_of("info") {
-                                    0 => if let Some(contents) = Lists::show(list, false) {
-                                        println!("{}", contents);
+                                    0 => if !crates.is_empty() {
+                                        let names: Vec<String> = crates.iter()
+                                            .map(|c| c.green().to_string()).collect();
+                                        println!("{}", names.join(", "));
                                     }
                                     // TODO: move this to 2 or more occurences, and
                                     // make a more compact presentation for 1 occurrence
-                                    1 | _ => if let Some(contents) = Lists::show(list, true) {
-                                        for crate_name in contents.split_whitespace() {
-                                            let _ = act.show_crate(crate_name,
-                                                args.occurrences_of("reverse"));
-                                            println!("");
-                                        }
+                                    1 | _ => for crate_name in crates.iter() {
+                                        let _ = act.show_crate(crate_name,
+                                            args.occurrences_of("reverse"), false);
+                                        println!("");
                                     }
                                 }
 
@@ -346,15 +1004,82 @@ impl<'a> CliArguments<'a> {
                     },
                     ("del", Some(args)) => {
                         // TODO: allow multiple
-                        if let Some(list) = args.value_of("list") { Lists::del(list); }
+                        if let Some(list) = args.value_of("list") {
+                            Lists::del(list, args.is_present("force"), args.is_present("yes"));
+                        }
+                    },
+                    ("rename", Some(args)) => {
+                        Lists::rename(args.value_of("old").unwrap(), args.value_of("new").unwrap());
                     },
                     ("add", Some(args)) => {
                         // TODO: allow multiple
-                        Lists::add(args.value_of("list").unwrap(), args.value_of("crate").unwrap());
+                        let list = args.value_of("list").map(String::from)
+                            .or_else(|| picker::pick("list:", &Lists::names(), false));
+                        let crat = list.as_ref().and_then(|_| args.value_of("crate").map(String::from)
+                            .or_else(|| picker::pick("crate:", &Cache::known_crate_names(), true)));
+
+                        match (list, crat) {
+                            (Some(list), Some(crat)) => Lists::add(&list, &crat),
+                            _ => println!("Error: the list and the crate are required.\n\n{}",
+                                args.usage()),
+                        }
                     },
                     ("rem", Some(args)) => {
                         // TODO: allow multiple
-                        Lists::rem(args.value_of("list").unwrap(), args.value_of("crate").unwrap());
+                        let list = args.value_of("list").map(String::from)
+                            .or_else(|| picker::pick("list:", &Lists::names(), false));
+                        let crat = list.as_ref().and_then(|list| args.value_of("crate").map(String::from)
+                            .or_else(|| picker::pick("crate:",
+                                &Lists::crates(list).unwrap_or_default(), false)));
+
+                        match (list, crat) {
+                            (Some(list), Some(crat)) => Lists::rem(&list, &crat),
+                            _ => println!("Error: the list and the crate are required.\n\n{}",
+                                args.usage()),
+                        }
+                    },
+                    ("sort", Some(args)) => {
+                        let list = args.value_of("list").unwrap();
+                        let _lock = Settings::lock();
+                        if let Some(mut crates) = Lists::crates(list) {
+                            let by = args.value_of("by").unwrap();
+                            act.sort_crates(&mut crates, by);
+                            println!("Sorted the list \"{}\" by {}.", list.bright_green(), by);
+                            Lists::set(list, &crates);
+                        } else {
+                            println!("List \"{}\" doesn't exist.", list.red());
+                        }
+                    },
+                    ("dedupe", Some(args)) => { Lists::dedupe(args.value_of("list")); },
+                    ("export", Some(args)) => {
+                        let format = args.value_of("format").unwrap_or(
+                            match Config::current().output {
+                                Output::Json => "json",
+                                Output::Text => "txt",
+                            });
+                        let _ = act.export_list(args.value_of("list").unwrap(), format);
+                    },
+                    ("import", Some(args)) => {
+                        Lists::import(Path::new(args.value_of("file").unwrap()),
+                            args.value_of("into"), args.value_of("format"),
+                            args.is_present("replace"));
+                    },
+                    ("watch", Some(args)) => {
+                        match act.watch_list(args.value_of("list").unwrap(),
+                            args.value_of("since").and_then(parse_date)) {
+                            Ok(true) => process::exit(1),
+                            Ok(false) => (),
+                            Err(e) => {
+                                println!("Error: {}", e);
+                                process::exit(2);
+                            }
+                        }
+                    },
+                    ("from-manifest", Some(args)) => {
+                        let include: Vec<&str> = args.values_of("include")
+                            .map(|v| v.collect()).unwrap_or_default();
+                        Lists::from_manifest(Path::new(args.value_of("path").unwrap()),
+                            args.value_of("name").unwrap(), &include);
                     },
                     /*
                     // TODO:
@@ -405,6 +1130,46 @@ impl<'a> CliArguments<'a> {
                 }
             }
 
+            // COMPLETIONS
+
+            ("completions", Some(args)) => {
+                let shell = args.value_of("shell").unwrap();
+                completions::generate(Self::app(), shell, &mut io::stdout());
+            },
+            ("man", Some(args)) => {
+                print!("{}", manual::generate(&Self::app(), args.value_of("format").unwrap()));
+            },
+            ("complete", Some(args)) => {
+                let names = match (args.value_of("what"), args.value_of("list")) {
+                    (Some("lists"), _) => Lists::names(),
+                    (_, Some(list)) => Lists::crates(list).unwrap_or_default(),
+                    _ => Cache::known_crate_names(),
+                };
+                for name in names {
+                    println!("{}", name);
+                }
+            },
+
+            // CONFIG ARGUMENTS
+
+            ("config", Some(config_matches)) => {
+                match config_matches.subcommand() {
+                    ("path", Some(_)) => {
+                        println!("{}", Settings::file().display());
+                        if let Some(local) = Settings::local_file() {
+                            println!("{}", local.display());
+                        }
+                    },
+                    ("get", Some(args)) => Settings::get(args.value_of("key").unwrap()),
+                    ("set", Some(args)) => Settings::set(args.value_of("key").unwrap(),
+                        args.value_of("value").unwrap()),
+                    ("unset", Some(args)) => Settings::unset(args.value_of("key").unwrap()),
+                    ("edit", Some(_)) => Settings::edit(),
+                    ("doctor", Some(args)) => { Doctor::check(args.is_present("fix")); },
+                    _ => unreachable!(),
+                }
+            }
+
             _ => println!("{} help", APPNAME),
 
         }
@@ -0,0 +1,119 @@
+use crate::APPNAME;
+
+use std::io::Write;
+
+use clap::{App, Shell};
+
+/// The `list` subcommands whose first argument is a list
+const LIST_SUBCOMMANDS: &str = "show add rem remove del delete rename sort dedupe export watch";
+
+/// Completes the names of the lists and crates in bash
+const BASH_DYNAMIC: &str = r#"
+_crin_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    case "${COMP_WORDS[1]}" in
+        list|lists)
+            if [[ ${COMP_CWORD} -eq 3 && " {list_subcommands} " == *" ${COMP_WORDS[2]} "* ]]; then
+                COMPREPLY=( $(compgen -W "$(crin complete lists 2>/dev/null)" -- "${cur}") )
+                return 0
+            elif [[ ${COMP_CWORD} -eq 4 && "${COMP_WORDS[2]}" == "add" ]]; then
+                COMPREPLY=( $(compgen -W "$(crin complete crates 2>/dev/null)" -- "${cur}") )
+                return 0
+            elif [[ ${COMP_CWORD} -eq 4 && "${COMP_WORDS[2]}" =~ ^(rem|remove)$ ]]; then
+                COMPREPLY=( $(compgen -W "$(crin complete crates --list "${COMP_WORDS[3]}" 2>/dev/null)" -- "${cur}") )
+                return 0
+            fi
+            ;;
+        show|crate|info)
+            if [[ ${COMP_CWORD} -eq 2 ]]; then
+                COMPREPLY=( $(compgen -W "$(crin complete crates 2>/dev/null)" -- "${cur}") )
+                return 0
+            fi
+            ;;
+    esac
+    _crin "$@"
+}
+
+complete -F _crin_dynamic -o bashdefault -o default crin
+"#;
+
+/// Completes the names of the lists and crates in zsh
+const ZSH_DYNAMIC: &str = r#"
+_crin_dynamic() {
+    case "${words[2]}" in
+        (list|lists)
+            if (( CURRENT == 4 )) && [[ " {list_subcommands} " == *" ${words[3]} "* ]]; then
+                compadd -- ${(f)"$(crin complete lists 2>/dev/null)"}
+                return
+            elif (( CURRENT == 5 )) && [[ "${words[3]}" == "add" ]]; then
+                compadd -- ${(f)"$(crin complete crates 2>/dev/null)"}
+                return
+            elif (( CURRENT == 5 )) && [[ "${words[3]}" == (rem|remove) ]]; then
+                compadd -- ${(f)"$(crin complete crates --list "${words[4]}" 2>/dev/null)"}
+                return
+            fi
+            ;;
+        (show|crate|info)
+            if (( CURRENT == 3 )); then
+                compadd -- ${(f)"$(crin complete crates 2>/dev/null)"}
+                return
+            fi
+            ;;
+    esac
+    _crin "$@"
+}
+
+_crin_dynamic "$@"
+"#;
+
+/// Completes the names of the lists and crates in fish
+const FISH_DYNAMIC: &str = r#"
+function __crin_nargs
+    count (commandline -opc)
+end
+complete -c crin -n "__fish_seen_subcommand_from list lists; and __fish_seen_subcommand_from {list_subcommands}; and test (__crin_nargs) -eq 3" -f -a "(crin complete lists 2>/dev/null)"
+complete -c crin -n "__fish_seen_subcommand_from list lists; and __fish_seen_subcommand_from add; and test (__crin_nargs) -eq 4" -f -a "(crin complete crates 2>/dev/null)"
+complete -c crin -n "__fish_seen_subcommand_from list lists; and __fish_seen_subcommand_from rem remove; and test (__crin_nargs) -eq 4" -f -a "(crin complete crates --list (commandline -opc)[4] 2>/dev/null)"
+complete -c crin -n "__fish_seen_subcommand_from show crate info; and test (__crin_nargs) -eq 2" -f -a "(crin complete crates 2>/dev/null)"
+"#;
+
+/// Generates the completion script for a shell.
+///
+/// The bash, zsh and fish scripts are extended to complete the names of the
+/// lists and crates dynamically, by calling the hidden `crin complete` command.
+pub fn generate<W: Write>(mut app: App, shell: &str, out: &mut W) {
+    let mut script = Vec::new();
+    let for_shell = match shell {
+        "bash" => Shell::Bash,
+        "zsh" => Shell::Zsh,
+        "fish" => Shell::Fish,
+        "powershell" => Shell::PowerShell,
+        _ => Shell::Elvish,
+    };
+    app.gen_completions_to(APPNAME, for_shell, &mut script);
+    let mut script = String::from_utf8_lossy(&script).into_owned();
+
+    match shell {
+        "bash" => {
+            // replace the static registration with the dynamic one
+            let static_line = format!("complete -F _{0} -o bashdefault -o default {0}", APPNAME);
+            script = script.replace(&static_line, "");
+            script.push_str(&BASH_DYNAMIC.replace("{list_subcommands}", LIST_SUBCOMMANDS));
+        },
+        "zsh" => {
+            // call the dynamic function instead of the static one
+            let static_call = format!("_{} \"$@\"", APPNAME);
+            if script.trim_end().ends_with(&static_call) {
+                let len = script.trim_end().len() - static_call.len();
+                script.truncate(len);
+            }
+            script.push_str(&ZSH_DYNAMIC.replace("{list_subcommands}", LIST_SUBCOMMANDS));
+        },
+        "fish" => {
+            script.push_str(&FISH_DYNAMIC.replace("{list_subcommands}", LIST_SUBCOMMANDS));
+        },
+        _ => (),
+    }
+
+    let _ = out.write_all(script.as_bytes());
+}
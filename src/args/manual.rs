@@ -0,0 +1,111 @@
+use crate::{APPNAME, VERSION};
+
+use clap::{App, AppSettings};
+
+// clap 2 has no public accessors for the subcommands, their descriptions and
+// aliases, so they are read from its hidden `App::p` parser. That's why clap is
+// pinned to an exact version in Cargo.toml, which must be checked when updated.
+
+/// Generates the manual from the CLI definition, as either a roff man page
+/// or a Markdown command reference, documenting every subcommand and alias
+pub fn generate(app: &App, format: &str) -> String {
+    let mut out = String::new();
+
+    if format == "markdown" {
+        out.push_str(&format!("# {} command reference\n\n", APPNAME));
+        if let Some(about) = app.p.meta.about {
+            out.push_str(&format!("{}\n\n", about));
+        }
+        out.push_str(&format!("```text\n{}\n```\n", help(app, APPNAME)));
+        for sub in visible_subcommands(app) {
+            markdown_section(&mut out, sub, APPNAME, 2);
+        }
+    } else {
+        out.push_str(&format!(".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+            APPNAME.to_uppercase(), APPNAME, VERSION));
+        out.push_str(&format!(".SH NAME\n{} \\- {}\n", APPNAME,
+            roff_escape(app.p.meta.about.unwrap_or(""))));
+        out.push_str(&format!(".SH SYNOPSIS\n.B {}\n[OPTIONS] <SUBCOMMAND>\n", APPNAME));
+        out.push_str(&format!(".SH OPTIONS\n.nf\n{}\n.fi\n", roff_escape(&help(app, APPNAME))));
+        out.push_str(".SH COMMANDS\n");
+        for sub in visible_subcommands(app) {
+            roff_section(&mut out, sub, APPNAME);
+        }
+    }
+    out
+}
+
+/// Adds the Markdown section of a subcommand, and of its own subcommands
+fn markdown_section(out: &mut String, app: &App, parent: &str, level: usize) {
+    let path = format!("{} {}", parent, app.get_name());
+
+    out.push_str(&format!("\n{} `{}`\n\n", "#".repeat(level), path));
+    if let Some(about) = app.p.meta.about {
+        out.push_str(&format!("{}\n\n", about));
+    }
+    let aliases = visible_aliases(app);
+    if !aliases.is_empty() {
+        let aliases: Vec<String> = aliases.iter().map(|a| format!("`{}`", a)).collect();
+        out.push_str(&format!("Aliases: {}\n\n", aliases.join(", ")));
+    }
+    out.push_str(&format!("```text\n{}\n```\n", help(app, &path)));
+
+    for sub in visible_subcommands(app) {
+        markdown_section(out, sub, &path, level + 1);
+    }
+}
+
+/// Adds the roff section of a subcommand, and of its own subcommands
+fn roff_section(out: &mut String, app: &App, parent: &str) {
+    let path = format!("{} {}", parent, app.get_name());
+
+    out.push_str(&format!(".SS \"{}\"\n", path));
+    if let Some(about) = app.p.meta.about {
+        out.push_str(&format!("{}\n", roff_escape(about)));
+    }
+    let aliases = visible_aliases(app);
+    if !aliases.is_empty() {
+        out.push_str(&format!(".PP\nAliases: {}\n", aliases.join(", ")));
+    }
+    out.push_str(&format!(".PP\n.nf\n{}\n.fi\n", roff_escape(&help(app, &path))));
+
+    for sub in visible_subcommands(app) {
+        roff_section(out, sub, &path);
+    }
+}
+
+/// Returns the help message of a (sub)command, using its full path in the usage
+fn help(app: &App, path: &str) -> String {
+    let mut buf = Vec::new();
+    let _ = app.clone().bin_name(path).write_help(&mut buf);
+    String::from_utf8_lossy(&buf).trim_end().to_string()
+}
+
+/// Returns the subcommands that are not hidden, skipping the generated `help`
+fn visible_subcommands<'x, 'a, 'b>(app: &'x App<'a, 'b>) -> Vec<&'x App<'a, 'b>> {
+    app.p.subcommands.iter()
+        .filter(|s| !s.p.is_set(AppSettings::Hidden) && s.get_name() != "help")
+        .collect()
+}
+
+/// Returns the visible aliases of a subcommand
+fn visible_aliases<'b>(app: &App<'_, 'b>) -> Vec<&'b str> {
+    app.p.meta.aliases.as_ref()
+        .map(|aliases| aliases.iter().filter(|a| a.1).map(|a| a.0).collect())
+        .unwrap_or_default()
+}
+
+/// Escapes the text for roff, so that lines are not taken as requests
+fn roff_escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,131 @@
+use super::{ORGANIZATION, APPNAME};
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::conf::{Config, Lists};
+
+const NAMESFILE: &'static str = "crate_names.txt";
+const RESPONSESDIR: &'static str = "responses";
+
+/// Container of methods to manage the local cache of crate names,
+/// used for completing them in the shell and in the prompts,
+/// and of the responses of the registry API
+pub struct Cache {}
+
+impl Cache {
+
+    /// Returns the cache directory, which is [OS dependant](https://crates.io/crates/directories)
+    pub fn dir() -> PathBuf {
+        directories::ProjectDirs::from("rs", ORGANIZATION, APPNAME)
+        .expect("Unable to retrieve app cache directory").cache_dir().to_owned()
+    }
+
+    /// Returns the cached crate names that haven't expired, sorted
+    pub fn crate_names() -> Vec<String> {
+        let mut names: Vec<String> = Self::read().into_iter().map(|(name, _)| name).collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the cached crate names and the ones in the lists, sorted and deduplicated
+    pub fn known_crate_names() -> Vec<String> {
+        let mut names = Self::crate_names();
+        for list in Lists::names() {
+            names.extend(Lists::crates(&list).unwrap_or_default());
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Adds crate names to the cache, or refreshes them if they were already there
+    pub fn remember<'a, I: IntoIterator<Item = &'a str>>(names: I) {
+        let now = Utc::now().timestamp();
+        let mut entries = Self::read();
+
+        for name in names {
+            match entries.iter_mut().find(|(n, _)| n == name) {
+                Some(entry) => entry.1 = now,
+                None => entries.push((name.to_string(), now)),
+            }
+        }
+
+        let contents: String = entries.iter()
+            .map(|(name, time)| format!("{}\t{}\n", name, time)).collect();
+
+        // the cache is not essential, so errors are ignored
+        let _ = fs::create_dir_all(Self::dir())
+            .and_then(|_| fs::write(Self::dir().join(NAMESFILE), contents));
+    }
+
+    /// Reads the cached names with the time they were last seen,
+    /// discarding the expired ones
+    fn read() -> Vec<(String, i64)> {
+        let oldest = Utc::now().timestamp() - Config::current().names_ttl as i64;
+
+        fs::read_to_string(Self::dir().join(NAMESFILE)).unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split('\t');
+                let name = parts.next()?;
+                let time = parts.next()?.parse::<i64>().ok()?;
+                if time >= oldest { Some((name.to_string(), time)) } else { None }
+            })
+            .collect()
+    }
+
+    /// Returns the cached response of an API url, if it hasn't expired
+    pub fn response(url: &str) -> Option<Value> {
+        let ttl = Config::current().responses_ttl as i64;
+        if ttl == 0 {
+            return None;
+        }
+        let text = fs::read_to_string(Self::response_file(url)).ok()?;
+        let mut cached: Value = serde_json::from_str(&text).ok()?;
+
+        // the url is checked in case of a collision of the file names
+        let time = cached["time"].as_i64()?;
+        if cached["url"] == url && time >= Utc::now().timestamp() - ttl {
+            cached.get_mut("body").map(Value::take)
+        } else {
+            None
+        }
+    }
+
+    /// Caches the response of an API url, removing the expired responses
+    pub fn store_response(url: &str, body: &Value) {
+        let ttl = Config::current().responses_ttl;
+        if ttl == 0 {
+            return;
+        }
+        let dir = Self::dir().join(RESPONSESDIR);
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.filter_map(Result::ok) {
+                let expired = entry.metadata().and_then(|m| m.modified()).ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .map_or(false, |age| age.as_secs() > ttl);
+                if expired {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        let cached = json!({ "url": url, "time": Utc::now().timestamp(), "body": body });
+        // the cache is not essential, so errors are ignored
+        let _ = fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(Self::response_file(url), cached.to_string()));
+    }
+
+    /// Returns the file where the response of an API url is cached
+    fn response_file(url: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        Self::dir().join(RESPONSESDIR).join(format!("{:016x}.json", hasher.finish()))
+    }
+}
@@ -1,19 +1,37 @@
-use super::{ORGANIZATION, APPNAME, CONFIGNAME};
+use super::{ORGANIZATION, APPNAME, CONFIGNAME, LOCALCONFIGNAME};
 
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
+use std::env;
 use std::path::PathBuf;
+use std::process::Command;
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use toml_edit::Document;
+use chrono::Local;
+use fs2::FileExt;
+use toml_edit::{value, Document, Item, Table, Value};
 use colored::*;
 
+use crate::util::confirm;
+
 lazy_static! {
     static ref SETTINGS: RwLock<Document> = RwLock::new(Document::new());
+    /// The configuration file, when overriden with `--config` or `CRIN_CONFIG`
+    static ref CONFIG_FILE: RwLock<Option<PathBuf>> = RwLock::new(None);
+    /// The project-local configuration file and its settings
+    static ref LOCAL: RwLock<Option<(PathBuf, Document)>> = RwLock::new(None);
+    /// The contents of the config file when it was last read or written
+    static ref LOADED: RwLock<Option<String>> = RwLock::new(None);
 }
 
+/// The number of nested locks held over the configuration by this process
+static LOCK_DEPTH: AtomicUsize = AtomicUsize::new(0);
+
 mod lists; pub use self::lists::Lists;
+mod doctor; pub use self::doctor::Doctor;
+mod schema; pub use self::schema::{Config, HealthThresholds, LicensePolicy, Output, Theme};
 
 pub struct Settings {}
 
@@ -24,24 +42,79 @@ impl Settings {
         .expect("Unable to retrieve app config directory").config_dir().to_owned()
     }
 
-    /// Reads the configuration from the user config file
+    /// Returns the path to the user configuration file
+    pub fn file() -> PathBuf {
+        if let Some(ref path) = *CONFIG_FILE.read().unwrap() {
+            return path.clone();
+        }
+        Self::dir().join(CONFIGNAME)
+    }
+
+    /// Returns the path to the project-local configuration file, if any
+    pub fn local_file() -> Option<PathBuf> {
+        LOCAL.read().unwrap().as_ref().map(|(path, _)| path.clone())
+    }
+
+    /// Returns a path next to the configuration file, decorating its name
+    fn sibling(prefix: &str, suffix: &str) -> PathBuf {
+        let file = Self::file();
+        let name = file.file_name().and_then(|n| n.to_str()).unwrap_or(CONFIGNAME).to_string();
+        file.with_file_name(format!("{}{}{}", prefix, name, suffix))
+    }
+
+    /// Initializes the settings, from either the provided configuration file
+    /// or the default one, and from the nearest project-local `.crin.toml`.
+    ///
+    /// The project-local settings are layered over the user ones when reading
+    /// them, except for the lists, which always live in the user config.
+    /// Then the typed settings are loaded from the result.
+    pub fn init(config_file: Option<PathBuf>) {
+        *CONFIG_FILE.write().unwrap() = config_file;
+        Self::read();
+
+        if let Some(path) = Self::find_local() {
+            match fs::read_to_string(&path).map(|t| t.parse::<Document>()) {
+                Ok(Ok(doc)) => *LOCAL.write().unwrap() = Some((path, doc)),
+                Ok(Err(e)) => eprintln!("Warning: ignoring the invalid file \"{}\":\n{}",
+                    path.display(), e),
+                Err(e) => eprintln!("Warning: couldn't read \"{}\":\n{}", path.display(), e),
+            }
+        }
+
+        // invalid settings are reported and replaced by their defaults
+        let (config, errors) = Config::from_doc(&Self::layered());
+        for e in errors {
+            eprintln!("{} {}", "Warning:".bright_red(), e);
+        }
+        config.install();
+    }
+
+    /// Finds the nearest project-local configuration file,
+    /// walking up from the current directory
+    fn find_local() -> Option<PathBuf> {
+        let mut dir = env::current_dir().ok()?;
+        loop {
+            let file = dir.join(LOCALCONFIGNAME);
+            if file.is_file() {
+                return Some(file);
+            }
+            if !dir.pop() {
+                return None;
+            }
+        }
+    }
+
+    /// Reads the configuration from the user config file.
+    ///
+    /// The configuration directory is not created until the first write.
     pub fn read() {
         let mut settings = SETTINGS.write().unwrap();
 
         // The configuration file path is [OS dependant](https://crates.io/crates/directories)
-        let config_path = Self::dir();
-        let config_file = config_path.join(CONFIGNAME);
+        let config_file = Self::file();
 
-        if config_path.exists() {
-            if !config_path.is_dir() {
-                panic!("configuration path exists, but is not a directory:\n{}",
-                       config_path.display());
-            }
-        } else {
-            // TODO don't create it by default
-            if let Err(e) = fs::create_dir_all(config_path) {
-                println!("Error: failed to create the configuration directory:\n{}", e);
-            }
+        if config_file.exists() && !config_file.is_file() {
+            panic!("configuration path exists, but is not a file:\n{}", config_file.display());
         }
 
         // Add in settings from the configuration file
@@ -54,28 +127,321 @@ impl Settings {
 
             let toml_doc = toml.parse::<Document>().expect("invalid toml");
             let _ = std::mem::replace(&mut *settings, toml_doc);
+            *LOADED.write().unwrap() = Some(toml);
+        } else {
+            let _ = std::mem::replace(&mut *settings, Document::new());
+            *LOADED.write().unwrap() = None;
+        }
+    }
 
+    /// Returns a copy of the user settings with the project-local ones layered over
+    pub fn layered() -> Document {
+        let mut doc = SETTINGS.read().unwrap().to_string().parse::<Document>()
+            .expect("Error: couldn't parse the configuration.");
+
+        if let Some((_, ref local)) = *LOCAL.read().unwrap() {
+            for (key, item) in local.as_table().iter() {
+                if key != "lists" {
+                    Self::merge(doc.as_table_mut().entry(key), item);
+                }
+            }
+        }
+        doc
+    }
+
+    /// Merges an item over another, recursing into their tables
+    fn merge(base: &mut Item, over: &Item) {
+        if base.as_table().is_some() && over.as_table().is_some() {
+            let base_table = base.as_table_mut().unwrap();
+            for (key, item) in over.as_table().unwrap().iter() {
+                Self::merge(base_table.entry(key), item);
+            }
         } else {
-            // println!("No configuration file exists. Creating a new one...");
-            // if let Err(e) = fs::write(config_file, "") {
-            //     println!("Error: failed to create a new configuration file:\n{}", e);
-            // }
+            *base = over.clone();
         }
     }
 
-    /// Writes the updated settings to the configuration file
+    /// Writes the updated settings to the configuration file.
+    ///
+    /// The settings are written to a temporary file that then replaces the
+    /// configuration, so that it's never left half written. Nothing is saved
+    /// if the file was modified by someone else since it was last read.
     pub fn write() {
+        if let Err(e) = Self::try_write() {
+            println!("{}", e.bright_red());
+        }
+    }
+
+    /// Writes the updated settings like `write`, returning the error message
+    /// instead of printing it
+    pub fn try_write() -> Result<(), String> {
 
-        let config_file = Self::dir().join(CONFIGNAME);
+        let config_file = Self::file();
+        let tmp_file = Self::sibling(".", ".tmp");
         let settings = SETTINGS.read().unwrap();
+        let mut loaded = LOADED.write().unwrap();
 
-        if let Ok(mut file) = fs::OpenOptions::new().write(true).create(true).open(config_file) {
-            file.set_len(0).unwrap();
+        if fs::read_to_string(&config_file).ok() != *loaded {
+            return Err("Error: the configuration file was modified externally \
+                since it was read. The changes were not saved.".to_string());
+        }
 
-            file.write(settings.to_string().as_bytes())
-                .expect("Error: couldn't write to the configuration file.");
-        } else {
-            println!("{}", "Error: Couldn't save the configuration.".bright_red());
+        let contents = settings.to_string();
+        let result = Self::create_dir()
+            .and_then(|_| File::create(&tmp_file))
+            .and_then(|mut file| {
+                file.write_all(contents.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_file, &config_file));
+
+        match result {
+            Ok(_) => {
+                *loaded = Some(contents);
+                Ok(())
+            },
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_file);
+                Err(format!("Error: Couldn't save the configuration. {}", e))
+            }
+        }
+    }
+
+    /// Acquires an exclusive advisory lock over the configuration, waiting for
+    /// other crin processes to release it, and reloads the settings so that the
+    /// read-modify-write cycle is made over the latest contents.
+    ///
+    /// The lock is released when the returned guard is dropped. Nested locks
+    /// are allowed, and only the outermost one locks and reloads.
+    ///
+    /// The lock file lives next to the configuration, so taking the lock creates
+    /// its directory: it must only be taken by the commands that write.
+    pub fn lock() -> ConfigLock {
+        Self::try_lock().unwrap_or_else(|e| {
+            eprintln!("Warning: couldn't lock the configuration file:\n{}", e);
+            LOCK_DEPTH.fetch_add(1, Ordering::SeqCst);
+            Self::read();
+            ConfigLock { file: None }
+        })
+    }
+
+    /// Acquires the lock like `lock`, but returns an error instead of
+    /// going on without it when it can't be acquired
+    pub fn try_lock() -> std::io::Result<ConfigLock> {
+        if LOCK_DEPTH.fetch_add(1, Ordering::SeqCst) > 0 {
+            return Ok(ConfigLock { file: None });
+        }
+
+        let lock_file = Self::sibling("", ".lock");
+        let file = Self::create_dir()
+            .and_then(|_| fs::OpenOptions::new().write(true).create(true).open(&lock_file))
+            .and_then(|file| file.lock_exclusive().map(|_| file));
+
+        match file {
+            Ok(file) => {
+                Self::read();
+                Ok(ConfigLock { file: Some(file) })
+            },
+            Err(e) => {
+                LOCK_DEPTH.fetch_sub(1, Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+
+    /// Prints the value of a setting, given its dotted key path,
+    /// taking into account the project-local settings.
+    ///
+    /// Tables are printed recursively, one `key.path = value` per line.
+    pub fn get(key_path: &str) {
+        let settings = Self::layered();
+
+        match Self::item(&settings, key_path) {
+            Some(item) => Self::print_item(key_path, item),
+            None => println!("The setting \"{}\" doesn't exist.", key_path.red()),
+        }
+    }
+
+    /// Sets the value of a setting, given its dotted key path.
+    ///
+    /// The value is parsed as TOML (a number, a boolean, an array...)
+    /// and stored as a string otherwise. Missing tables are created.
+    /// Values that are not valid for a known setting are rejected.
+    pub fn set(key_path: &str, new_value: &str) {
+        let _lock = Self::lock();
+        let (_, previous) = Config::from_doc(&Self::layered());
+
+        let parsed = new_value.parse::<Value>()
+            .unwrap_or_else(|_| Value::from(new_value));
+        {
+            let mut settings = SETTINGS.write().unwrap();
+            if let Err(e) = Self::set_value(&mut *settings, key_path, parsed) {
+                println!("Error: {}", e);
+                return;
+            }
+        }
+
+        // the errors of this setting, and the new ones it causes in others
+        let (_, errors) = Config::from_doc(&Self::layered());
+        let errors: Vec<_> = errors.iter()
+            .filter(|e| e.key == key_path || !previous.iter().any(|p| p.key == e.key))
+            .collect();
+        if !errors.is_empty() {
+            for e in errors {
+                println!("Error: {}", e);
+            }
+            Self::read(); // discard the change
+            return;
+        }
+
+        println!("{} = {}", key_path.bright_green(), new_value.yellow());
+        Self::write();
+    }
+
+    /// Sets a value in a document, given its dotted key path,
+    /// creating the missing tables and keeping the existing formatting
+    fn set_value(doc: &mut Document, key_path: &str, new_value: Value) -> Result<(), String> {
+        let keys: Vec<&str> = key_path.split('.').collect();
+        let (last, parents) = keys.split_last().expect("the key path can't be empty");
+        let mut table = doc.as_table_mut();
+
+        for key in parents {
+            let item = table.entry(key);
+            if item.is_none() {
+                *item = Item::Table(Table::new());
+            }
+            table = item.as_table_mut()
+                .ok_or_else(|| format!("the setting \"{}\" is not a table.", key))?;
+        }
+        *table.entry(last) = value(new_value);
+        Ok(())
+    }
+
+    /// Removes a setting, given its dotted key path
+    pub fn unset(key_path: &str) {
+        let _lock = Self::lock();
+
+        if Self::item(&SETTINGS.read().unwrap(), key_path).is_none() {
+            println!("The setting \"{}\" doesn't exist.", key_path.red());
+            return;
+        }
+
+        let keys: Vec<&str> = key_path.split('.').collect();
+        let (last, parents) = keys.split_last().expect("the key path can't be empty");
+        {
+            let mut settings = SETTINGS.write().unwrap();
+            let mut table = Some(settings.as_table_mut());
+
+            // all the parents exist, so this doesn't insert any new key
+            for key in parents {
+                table = table.and_then(|t| t.entry(key).as_table_mut());
+            }
+            if let Some(t) = table {
+                t.remove(last);
+            }
+        }
+        println!("Removed the setting \"{}\".", key_path.bright_green());
+        Self::write();
+    }
+
+    /// Opens the configuration file with the user's editor ($VISUAL or $EDITOR),
+    /// and restores the previous contents if the result is not valid TOML
+    pub fn edit() {
+        let _lock = Self::lock();
+
+        let config_file = Self::file();
+        let previous = fs::read_to_string(&config_file).unwrap_or_default();
+        if !config_file.exists() {
+            let created = Self::create_dir().and_then(|_| fs::write(&config_file, ""));
+            if let Err(e) = created {
+                println!("Error: failed to create the configuration file:\n{}", e);
+                return;
+            }
+        }
+
+        let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+
+        // the editor may include arguments, e.g. "code --wait"
+        let mut words = editor.split_whitespace();
+        let status = Command::new(words.next().unwrap_or("vi"))
+            .args(words)
+            .arg(&config_file)
+            .status();
+
+        match status {
+            Ok(s) if s.success() => (),
+            Ok(s) => { println!("Error: the editor \"{}\" exited with {}", editor, s); return; },
+            Err(e) => { println!("Error: couldn't run the editor \"{}\":\n{}", editor, e); return; },
+        }
+
+        let edited = fs::read_to_string(&config_file).unwrap_or_default();
+        if let Err(e) = edited.parse::<Document>() {
+            println!("{}\n{}", "Error: the configuration is not valid TOML:".bright_red(), e);
+            if confirm("Restore the previous configuration?") {
+                if let Err(e) = fs::write(&config_file, &previous) {
+                    println!("Error: couldn't restore the configuration:\n{}", e);
+                }
+            }
+        }
+    }
+
+    /// Returns the item at a dotted key path, if it exists
+    fn item<'a>(doc: &'a Document, key_path: &str) -> Option<&'a Item> {
+        let mut keys = key_path.split('.');
+        let mut item = &doc[keys.next().unwrap_or("")];
+        for key in keys {
+            item = &item[key];
+        }
+        if item.is_none() { None } else { Some(item) }
+    }
+
+    /// Prints an item, recursing into its tables
+    fn print_item(key_path: &str, item: &Item) {
+        if let Some(table) = item.as_table() {
+            for (key, child) in table.iter() {
+                Self::print_item(&format!("{}.{}", key_path, key), child);
+            }
+        } else if let Some(tables) = item.as_array_of_tables() {
+            for (i, table) in tables.iter().enumerate() {
+                for (key, child) in table.iter() {
+                    Self::print_item(&format!("{}.{}.{}", key_path, i, key), child);
+                }
+            }
+        } else if let Some(v) = item.as_value() {
+            println!("{} = {}", key_path, v.to_string().trim());
+        }
+    }
+
+    /// Creates the directory containing the configuration file, if it doesn't exist
+    fn create_dir() -> std::io::Result<()> {
+        match Self::file().parent() {
+            Some(dir) if !dir.as_os_str().is_empty() && !dir.exists() => fs::create_dir_all(dir),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copies the configuration file to a timestamped backup next to it
+    pub fn backup() -> std::io::Result<PathBuf> {
+        let config_file = Self::file();
+        let backup_file = Self::sibling("",
+            &format!(".{}.bak", Local::now().format("%Y%m%d%H%M%S")));
+
+        fs::copy(&config_file, &backup_file)?;
+        Ok(backup_file)
+    }
+}
+
+/// An advisory lock over the configuration file, released when dropped
+pub struct ConfigLock {
+    file: Option<File>,
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        if let Some(ref file) = self.file {
+            let _ = file.unlock();
         }
+        LOCK_DEPTH.fetch_sub(1, Ordering::SeqCst);
     }
 }
@@ -0,0 +1,189 @@
+use crate::conf::*;
+use crate::conf::schema::KNOWN_KEYS;
+use crate::util::is_valid_crate_name;
+use toml_edit::Array;
+
+/// A problem found in the configuration
+struct Problem {
+    message: String,
+    fixable: bool,
+}
+
+impl Problem {
+    fn fixable(message: String) -> Problem { Problem { message, fixable: true } }
+    fn warning(message: String) -> Problem { Problem { message, fixable: false } }
+}
+
+/// Container of methods to check and repair the config file
+pub struct Doctor {}
+
+impl Doctor {
+
+    /// Checks the configuration for problems, and optionally repairs them,
+    /// backing up the original file before writing any change
+    pub fn check(fix: bool) {
+        // only locked when fixing, since taking the lock creates the config directory
+        let _lock = if fix { Some(Settings::lock()) } else { None };
+
+        // the repairs are made over a copy, and only saved when fixing
+        let mut doc = SETTINGS.read().unwrap().to_string().parse::<Document>()
+            .expect("Error: couldn't parse the configuration.");
+        let mut problems = Vec::new();
+
+        Self::check_lists(&mut doc, &mut problems);
+        Self::check_settings(&mut doc, &mut problems);
+        Self::check_keys(&doc, &mut problems);
+
+        if problems.is_empty() {
+            println!("{}", "No problems found in the configuration.".green());
+            return;
+        }
+
+        let fixable = problems.iter().filter(|p| p.fixable).count();
+        for p in problems.iter() {
+            if p.fixable {
+                println!("{} {}", "fixable:".yellow(), p.message);
+            } else {
+                println!("{} {}", "warning:".bright_red(), p.message);
+            }
+        }
+        println!("\nFound {} problems, {} of them fixable.",
+            problems.len().to_string().bright_red(), fixable.to_string().yellow());
+
+        if fixable == 0 { return; }
+
+        if fix {
+            match Settings::backup() {
+                Ok(path) => println!("Saved a backup of the configuration in:\n{}",
+                    path.display().to_string().bright_blue()),
+                Err(e) => {
+                    println!("Error: couldn't back up the configuration, nothing was fixed:\n{}", e);
+                    return;
+                }
+            }
+            {
+                let mut settings = SETTINGS.write().unwrap();
+                let _ = std::mem::replace(&mut *settings, doc);
+            }
+            Settings::write();
+            println!("Fixed {} problems.", fixable.to_string().green());
+        } else {
+            println!("You can repair them with '{}'", "crin config doctor --fix".bright_blue());
+        }
+    }
+
+    /// Checks the `[lists]` table and each of the lists in it, repairing them
+    fn check_lists(doc: &mut Document, problems: &mut Vec<Problem>) {
+        if doc["lists"].is_none() { return; }
+
+        if doc["lists"].as_table().is_none() {
+            // an inline table can be converted, keeping its lists
+            let converted = doc["lists"].as_inline_table().map(|inline| {
+                let mut table = Item::Table(Table::new());
+                for (key, v) in inline.iter() {
+                    table[key] = Item::Value(v.clone());
+                }
+                table
+            });
+            match converted {
+                Some(table) => {
+                    problems.push(Problem::fixable(format!(
+                        "{} is an InlineTable, it will be converted to a Table",
+                        "lists".cyan())));
+                    doc["lists"] = table;
+                },
+                None => {
+                    problems.push(Problem::fixable(format!(
+                        "{} is {} instead of a Table, it will be replaced by an empty one",
+                        "lists".cyan(), Self::typeof_item(&doc["lists"]))));
+                    doc["lists"] = Item::Table(Table::new());
+                },
+            }
+        }
+
+        let names: Vec<String> = doc["lists"].as_table()
+            .map(|t| t.iter().map(|(name, _)| name.to_string()).collect())
+            .unwrap_or_default();
+
+        for name in names {
+            let list = &doc["lists"][name.as_str()];
+            let crates: Vec<String>;
+
+            if let Some(arr) = list.as_array() {
+                let mut valid: Vec<String> = Vec::new();
+                for v in arr.iter() {
+                    match v.as_str() {
+                        Some(c) if !is_valid_crate_name(c) => problems.push(Problem::fixable(
+                            format!("list {} contains an invalid crate name \"{}\"",
+                                name.cyan(), c.bright_red()))),
+                        Some(c) if valid.iter().any(|v| v == c) => problems.push(Problem::fixable(
+                            format!("list {} contains the crate \"{}\" more than once",
+                                name.cyan(), c.yellow()))),
+                        Some(c) => valid.push(c.to_string()),
+                        None => problems.push(Problem::fixable(
+                            format!("list {} contains {} instead of a crate name: {}",
+                                name.cyan(), Lists::typeof_value(Some(v)), v))),
+                    }
+                }
+                if valid.len() == arr.len() { continue; }
+                crates = valid;
+
+            } else if let Some(c) = list.as_str() {
+                problems.push(Problem::fixable(format!(
+                    "list {} is a String instead of an Array, it will be converted",
+                    name.cyan())));
+                crates = if is_valid_crate_name(c) { vec![c.to_string()] } else { Vec::new() };
+
+            } else {
+                problems.push(Problem::fixable(format!(
+                    "list {} is {} instead of an Array, it will be replaced by an empty one",
+                    name.cyan(), Self::typeof_item(list))));
+                crates = Vec::new();
+            }
+
+            let mut crates_arr = Array::default();
+            for c in crates.iter() {
+                crates_arr.push(c.as_str());
+            }
+            doc["lists"][name.as_str()] = value(crates_arr);
+        }
+    }
+
+    /// Checks the types and values of the rest of the known settings,
+    /// resetting the invalid ones to their defaults
+    fn check_settings(doc: &mut Document, problems: &mut Vec<Problem>) {
+        let (config, errors) = Config::from_doc(doc);
+        let mut changed = false;
+
+        // the lists were already checked
+        for e in errors.iter().filter(|e| !e.key.starts_with("lists")) {
+            problems.push(Problem::fixable(format!("{}, it will be reset to its default", e)));
+            changed = true;
+        }
+        if changed {
+            config.write_to(doc);
+
+            // the settings that depend on each other could still conflict
+            let (_, remaining) = Config::from_doc(doc);
+            for e in remaining.iter().filter(|e| !e.key.starts_with("lists")) {
+                problems.push(Problem::warning(format!("{}, and it couldn't be repaired", e)));
+            }
+        }
+    }
+
+    /// Checks for unknown keys in the rest of the configuration
+    fn check_keys(doc: &Document, problems: &mut Vec<Problem>) {
+        for (key, _) in doc.as_table().iter() {
+            if !KNOWN_KEYS.contains(&key) {
+                problems.push(Problem::warning(format!("unknown setting {}", key.cyan())));
+            }
+        }
+    }
+
+    /// Returns a string identifying the type of a TOML Item
+    pub fn typeof_item(item: &Item) -> &'static str {
+        if item.is_table() { return "a Table"; }
+        if item.is_array_of_tables() { return "an Array of Tables"; }
+        Lists::typeof_value(item.as_value())
+    }
+}
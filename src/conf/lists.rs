@@ -1,4 +1,7 @@
 use crate::conf::*;
+use crate::manifest;
+use crate::util::{confirm, is_valid_crate_name};
+use std::path::Path;
 use toml_edit::{value, Value, Array, Table};
 
 /// Container of methods to manage lists in the config file
@@ -6,7 +9,8 @@ pub struct Lists {}
 
 impl Lists {
 
-    /// Checks if a list already exists
+    /// Checks if a list already exists, even if it's not valid,
+    /// so that it can still be deleted or renamed
     pub fn exists(list: &str) -> bool {
         let settings = SETTINGS.read().unwrap();
 
@@ -23,54 +27,70 @@ impl Lists {
     /// Shows the crates contained in a list,
     /// either as plain text, or colored & separated by commas
     pub fn show(list: &str, plain: bool) -> Option<String> {
+        let crates = Self::crates(list);
+        if crates.is_none() && !Self::exists(list) {
+            println!("List \"{}\" doesn't exist.", list.red());
+        }
+        crates.map(|crates| if plain {
+            crates.join(" ")
+        } else {
+            crates.iter().map(|c| c.green().to_string()).collect::<Vec<_>>().join(", ")
+        })
+    }
 
-        if Self::exists(list) {
-            let settings = SETTINGS.read().unwrap();
+    /// Returns the crates contained in a list, or None if it's not a valid list
+    pub fn crates(list: &str) -> Option<Vec<String>> {
+        Self::typed().into_iter().find(|(name, _)| name == list).map(|(_, crates)| crates)
+    }
+
+    /// Returns the valid lists with their crates, as loaded by the typed settings
+    fn typed() -> Vec<(String, Vec<String>)> {
+        Config::lists_from(&SETTINGS.read().unwrap()).0
+    }
+
+    /// Replaces the contents of a list, creating it if it doesn't exist
+    pub fn set(list: &str, crates: &[String]) {
+        let _lock = Settings::lock();
+        if let Err(e) = Self::write_list(list, crates) {
+            println!("{}", e.bright_red());
+        }
+    }
 
-            if let Some(crates) = settings["lists"][list].as_array() {
+    /// Replaces the contents of a list like `set`, but without printing anything,
+    /// returning the error message instead
+    pub fn try_set(list: &str, crates: &[String]) -> Result<(), String> {
+        let _lock = Settings::try_lock()
+            .map_err(|e| format!("Error: couldn't lock the configuration file: {}", e))?;
+        Self::write_list(list, crates)
+    }
 
-                let mut crates_str = "".to_string();
+    /// Replaces the contents of a list and writes the settings, with the lock held
+    fn write_list(list: &str, crates: &[String]) -> Result<(), String> {
+        {
+            let mut settings = SETTINGS.write().unwrap();
 
-                if crates.is_empty() {
-                    return Some("".to_string());
-                } else {
-                    for crat in crates.iter() {
-                        if let Some(c) = crat.as_str() {
-                            if plain {
-                                crates_str = format!("{} {}", crates_str, c);
-                            } else {
-                                crates_str = format!("{}, {}", crates_str, c.green());
-                            }
-                        } else {
-                            println!("Error: couldn't parse crate name as String: \"{}\"", crat);
-                        }
-                    }
-                    return Some(crates_str[1..].trim().to_string()); // remove leading comma
-                }
+            if !settings.as_table().contains_table("lists") {
+                settings.as_table_mut()["lists"] = toml_edit::Item::Table(<Table>::new());
             }
-        } else {
-            println!("List \"{}\" doesn't exist.", list.red());
+            let mut crates_arr = Array::default();
+            for c in crates {
+                crates_arr.push(c.as_str());
+            }
+            settings["lists"][list] = value(crates_arr);
         }
-        None
+        Settings::try_write()
     }
 
     /// Returns the number of crates in a list
     pub fn quantity(list: &str) -> usize {
-        let settings = SETTINGS.read().unwrap();
-        if let Some(crates) = settings["lists"][list].as_array() {
-            crates.len()
-        } else {
-            // TODO: recreate list as empty?
-            // println!("Error: invalid format. list \"{}\" is not an Array, but {}",
-            //     list.red(), Self::typeof_value(settings["lists"][list].as_value()));
-            // println!("With the contents: {}", settings["lists"][list].as_value().unwrap());
-            0
-        }
+        // invalid lists can be repaired with `crin config doctor --fix`
+        Self::crates(list).map_or(0, |crates| crates.len())
     }
 
     /// Adds one crate to a list
     // TODO: allow adding multiple crates
     pub fn add(list: &str, crat: &str) {
+        let _lock = Settings::lock();
         if Self::exists(list) {
 
             let mut crates_vec: Vec<&str>;
@@ -85,11 +105,12 @@ impl Lists {
 
                 // } else if ... { // TODO: check if the crate is valid
                 } else {
+                    // keep the insertion order
                     let mut crates_arr = Array::default();
-                    crates_arr.push(crat);
                     for c in crates_vec {
                         crates_arr.push(c);
                     }
+                    crates_arr.push(crat);
 
                     {
                     let mut settings = SETTINGS.write().unwrap();
@@ -112,6 +133,7 @@ impl Lists {
 
     /// Creates a new list
     pub fn new(list: &str) {
+        let _lock = Settings::lock();
         if Self::exists(list) {
             println!("List \"{}\" already exists.", list.red());
         } else {
@@ -127,46 +149,177 @@ impl Lists {
         }
     }
 
-    /// Deletes an empty list
+    /// Deletes a list, which must be empty unless forced.
+    ///
+    /// Forcing asks for confirmation unless `yes` is true. It also allows
+    /// deleting lists with an invalid format.
     // TODO: allow deleting multiple lists
-    pub fn del(list: &str) {
-        if Self::exists(list) {
-            let mut changed = false;
+    pub fn del(list: &str, force: bool, yes: bool) {
+        if !Self::exists(list) {
+            println!("List \"{}\" doesn't exist.", list.red());
+            return;
+        }
+
+        let crates = Self::crates(list);
+        let valid = crates.is_some();
+        let quantity = Self::quantity(list);
+
+        if valid && quantity == 0 {
+            println!("Deleting the empty list \"{}\".", list.bright_green());
+
+        } else if force {
+            let question = if valid {
+                format!("Delete the list \"{}\" and its {} crates?", list.bright_red(), quantity)
+            } else {
+                format!("Delete the invalid list \"{}\"?", list.bright_red())
+            };
+            if !yes && !confirm(&question) {
+                println!("The list \"{}\" was not deleted.", list.green());
+                return;
+            }
+            println!("Deleting the list \"{}\".", list.bright_green());
+
+        } else {
+            if valid {
+                println!("The list \"{}\" cannot be deleted because it's not empty.",
+                    list.bright_red());
+            } else {
+                let settings = SETTINGS.read().unwrap();
+                println!("Error: invalid format. list \"{}\" is not an Array of crate names, \
+                    but {}", list.red(), Self::typeof_value(settings["lists"][list].as_value()));
+                if let Some(contents) = settings["lists"][list].as_value() {
+                    println!("With the contents: {}", contents);
+                }
+            }
+            println!("You can delete it anyway with '{}'",
+                format!("crin list del {} --force", list).bright_blue());
+            if !valid {
+                println!("Or repair it with '{}'", "crin config doctor --fix".bright_blue());
+            }
+            return;
+        }
+
+        // only lock after the confirmation, to not block other commands while waiting,
+        // and don't delete anything else than what was confirmed
+        let _lock = Settings::lock();
+        if !Self::exists(list) || Self::crates(list) != crates {
+            println!("The list \"{}\" changed in the meantime and was not deleted.", list.red());
+            return;
+        }
+        {
+            let mut settings = SETTINGS.write().unwrap();
+            if let Some(table) = settings["lists"].as_table_mut() {
+                table.remove(list);
+            }
+        }
+        Settings::write();
+    }
+
+    /// Renames a list, keeping its place, its crates and their formatting
+    pub fn rename(old: &str, new: &str) {
+        let _lock = Settings::lock();
+        if !Self::exists(old) {
+            println!("List \"{}\" doesn't exist.", old.red());
+        } else if Self::exists(new) {
+            println!("List \"{}\" already exists.", new.red());
+        } else {
             {
                 let mut settings = SETTINGS.write().unwrap();
 
-                if let Some(crates) = settings["lists"][list].as_array() {
-
-                    // Only delete the list if it's empty
-                    if crates.is_empty() {
-                        if let Some(table) = settings["lists"].as_table_mut() {
-                            println!("Deleting the empty list \"{}\".", list.bright_green());
-                            table.remove(list);
-                            changed = true;
-                        } else {
-                            println!("Error: couldn't delete the list \"{}\".", list.red());
+                // toml_edit can't rename a key, so it's renamed in the text, which keeps
+                // the comments and whitespace around it, as long as nothing else changed
+                let renamed = Self::rename_key(&settings.to_string(), old, new)
+                    .and_then(|text| text.parse::<Document>().ok())
+                    .filter(|doc| Self::lists_renamed(&settings, doc, old, new));
+
+                match renamed {
+                    Some(doc) => { let _ = std::mem::replace(&mut *settings, doc); },
+                    // otherwise all the lists are reinserted in their order,
+                    // which keeps the renamed one in its place, but not the comments
+                    None => if let Some(table) = settings["lists"].as_table_mut() {
+                        let names: Vec<String> = table.iter()
+                            .map(|(name, _)| name.to_string()).collect();
+                        for name in names {
+                            if let Some(item) = table.remove(&name) {
+                                *table.entry(if name == old { new } else { name.as_str() }) = item;
+                            }
                         }
-                    } else {
-                        println!("The list \"{}\" cannot be deleted because it's not empty.",
-                            list.bright_red());
-                    }
-                } else {
-                    println!("Error: invalid format. list \"{}\" is not an Array, but {}",
-                        list.red(), Self::typeof_value(settings["lists"][list].as_value()));
-                    println!("With the contents: {}", settings["lists"][list].as_value().unwrap());
-                    // TODO: will delete if provided with force argument
+                    },
                 }
+            }
+            println!("Renamed the list \"{}\" to \"{}\".", old.green(), new.bright_green());
+            Settings::write();
+        }
+    }
 
+    /// Renames the key of a list in the text of a configuration file,
+    /// leaving the rest of the text as it was.
+    ///
+    /// Returns None if the key isn't found in a `[lists]` table,
+    /// e.g. when the lists are in an inline table.
+    fn rename_key(text: &str, old: &str, new: &str) -> Option<String> {
+        let bare = |key: &str| !key.is_empty()
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let quoted = |key: &str| format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut forms = vec![quoted(old), format!("'{}'", old)];
+        if bare(old) {
+            forms.push(old.to_string());
+        }
+        let new_key = if bare(new) { new.to_string() } else { quoted(new) };
+
+        let mut in_lists = false;
+        let mut offset = 0;
+        for line in text.split('\n') {
+            let trimmed = line.trim_start();
+            let start = offset + line.len() - trimmed.len();
+            offset += line.len() + 1;
+
+            if trimmed.starts_with('[') {
+                // a table header, unless it's the continuation of a multiline array
+                let header = trimmed.find(']').map(|end| (&trimmed[1..end], &trimmed[end + 1..]));
+                if let Some((name, rest)) = header {
+                    let rest = rest.trim();
+                    if rest.is_empty() || rest.starts_with('#') {
+                        let name = name.trim();
+                        in_lists = name == "lists" || name == "\"lists\"";
+                    }
+                }
+                continue;
             }
-            if changed { Settings::write(); }
-        } else {
-            println!("List \"{}\" doesn't exist.", list.red());
+            if !in_lists {
+                continue;
+            }
+            for form in forms.iter() {
+                if trimmed.starts_with(form.as_str())
+                    && trimmed[form.len()..].trim_start().starts_with('=') {
+                    let end = start + form.len();
+                    return Some(format!("{}{}{}", &text[..start], new_key, &text[end..]));
+                }
+            }
+        }
+        None
+    }
+
+    /// Checks that the only difference in the lists of two documents
+    /// is that a list was renamed, in the same place
+    fn lists_renamed(before: &Document, after: &Document, old: &str, new: &str) -> bool {
+        let lists = |doc: &Document| doc["lists"].as_table().map(|t| t.iter()
+            .map(|(name, list)| (name.to_string(), list.as_value().map(|v| v.to_string())))
+            .collect::<Vec<_>>());
+
+        match (lists(before), lists(after)) {
+            (Some(before), Some(after)) => before.len() == after.len() && before.iter()
+                .zip(after.iter())
+                .all(|(b, a)| b.1 == a.1 && if b.0 == old { a.0 == new } else { a.0 == b.0 }),
+            _ => false,
         }
     }
 
     /// Removes a crate from a list
     // TODO: allow deleting multiple crates, maybe receiving a clap::Values struct
     pub fn rem(list: &str, crat: &str) {
+        let _lock = Settings::lock();
         if Self::exists(list) {
             let mut crates_vec: Vec<&str>;
 
@@ -203,49 +356,227 @@ impl Lists {
         }
     }
 
-    /// Show the saved lists
-    pub fn show_lists(recursive: bool) {
-        let settings = SETTINGS.read().unwrap();
+    /// Imports the crates from a file into a list.
+    ///
+    /// The format (json, txt or cargo) is guessed from the file extension when
+    /// not provided, and the list name defaults to the one stored in the JSON
+    /// file, or else to the file name. An existing invalid list is only replaced.
+    pub fn import(path: &Path, into: Option<&str>, format: Option<&str>, replace: bool) {
+        let _lock = Settings::lock();
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => {
+                println!("Error: couldn't read the file \"{}\":\n{}", path.display(), e);
+                return;
+            }
+        };
+
+        // any other file, like a Cargo.lock, would be read as crate names line by line
+        let format = match format.or_else(|| match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Some("json"),
+            Some("toml") => Some("cargo"),
+            Some("txt") => Some("txt"),
+            _ => None,
+        }) {
+            Some(f) => f,
+            None => {
+                println!("Error: couldn't guess the format of \"{}\". Provide it with '{}'",
+                    path.display(), "--format <json|txt|cargo>".bright_blue());
+                return;
+            }
+        };
+
+        let (name_hint, new_crates) = match Self::parse_import(&text, format) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                println!("Error: couldn't parse \"{}\" as {}:\n{}",
+                    path.display(), format, e);
+                return;
+            }
+        };
+
+        let list = match into.map(|l| l.to_string()).or(name_hint).or_else(|| {
+            path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string())
+        }) {
+            Some(l) => l,
+            None => {
+                println!("Error: couldn't guess the list name. Provide it with '{}'",
+                    "--into <list>".bright_blue());
+                return;
+            }
+        };
+
+        if !replace && Self::exists(&list) && Self::crates(&list).is_none() {
+            println!("The list \"{}\" is not valid, so the crates can't be added to it.",
+                list.bright_red());
+            println!("You can replace it with '{}'", "--replace".bright_blue());
+            println!("Or repair it with '{}'", "crin config doctor --fix".bright_blue());
+            return;
+        }
 
-        let err_msg = format!("You have no lists. Create a new one with '{}'",
-            "crin list new <listname>".bright_blue());
+        let mut crates = if replace { Vec::new() } else { Self::crates(&list).unwrap_or_default() };
+        let previous = crates.len();
+        for c in new_crates {
+            if !crates.contains(&c) {
+                crates.push(c);
+            }
+        }
 
-        if let Some(lists) = settings["lists"].as_table() {
-            if lists.len() > 0 {
+        if replace {
+            println!("Imported {} crates into the list \"{}\", replacing its contents.",
+                crates.len().to_string().cyan(), list.bright_green());
+        } else {
+            println!("Imported {} new crates into the list \"{}\".",
+                (crates.len() - previous).to_string().cyan(), list.bright_green());
+        }
+        Self::set(&list, &crates);
+    }
 
-                let mut lists_str = "".to_string();
+    /// Creates a new list from the dependencies of a Cargo.toml or Cargo.lock file
+    pub fn from_manifest(path: &Path, list: &str, include: &[&str]) {
+        let _lock = Settings::lock();
+        if Self::exists(list) {
+            println!("List \"{}\" already exists.", list.red());
+            return;
+        }
 
-                if recursive {
-                    // show also the contained crates
-                    for (list_name, _value) in lists.iter() {
-                        lists_str = format!("{}\n{} {}: {}",
-                            lists_str, list_name.bright_green(),
-                            format!("({})", Self::quantity(&list_name)).cyan(),
-                            if let Some(crates) = Self::show(list_name, false)
-                                { crates.normal() } else { "???".bright_red() }
-                        );
+        match manifest::dependencies(path, include) {
+            Ok(deps) => {
+                let mut crates: Vec<String> = Vec::new();
+                for d in deps {
+                    if !crates.contains(&d.name) {
+                        crates.push(d.name);
                     }
-                } else {
-                    // show just the lists with their number of crates
-                    for (list_name, _value) in lists.iter() {
-                        lists_str = format!("{}, {} {}",
-                            lists_str, list_name.bright_green(),
-                            format!("({})", Self::quantity(&list_name)).cyan()
-                        );
+                }
+                println!("Creating the list \"{}\" with {} crates.",
+                    list.bright_green(), crates.len().to_string().cyan());
+                Self::set(list, &crates);
+            },
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    /// Parses the contents of an imported file,
+    /// returning the name of the list (if any) and its crates
+    fn parse_import(text: &str, format: &str) -> Result<(Option<String>, Vec<String>), String> {
+        let (name, crates) = Self::parse_format(text, format)?;
+
+        let invalid: Vec<&str> = crates.iter().map(|c| c.as_str())
+            .filter(|c| !is_valid_crate_name(c)).collect();
+        if !invalid.is_empty() {
+            return Err(format!("invalid crate names: {}", invalid.iter()
+                .map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ")));
+        }
+        Ok((name, crates))
+    }
+
+    /// Parses the contents of an imported file in a format, without validating them
+    fn parse_format(text: &str, format: &str) -> Result<(Option<String>, Vec<String>), String> {
+        match format {
+            "json" => {
+                let json: serde_json::Value = serde_json::from_str(text)
+                    .map_err(|e| e.to_string())?;
+                let (name, crates) = match json {
+                    serde_json::Value::Object(ref obj) => (
+                        obj.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()),
+                        obj.get("crates").and_then(|c| c.as_array())
+                            .ok_or("missing the \"crates\" array")?,
+                    ),
+                    serde_json::Value::Array(ref arr) => (None, arr),
+                    _ => return Err("expected an array or an object".to_string()),
+                };
+                let crates = crates.iter().filter_map(|c| c.as_str())
+                    .map(|c| c.to_string()).collect();
+                Ok((name, crates))
+            },
+            "cargo" => {
+                let doc = text.parse::<Document>().map_err(|e| e.to_string())?;
+                let crates = manifest::manifest_dependencies(&doc, &["dev", "build"])
+                    .into_iter().map(|d| d.name).collect();
+                Ok((None, crates))
+            },
+            "txt" => {
+                // one crate per line, ignoring empty lines and #comments
+                let crates = text.lines()
+                    .filter_map(|line| line.split('#').next())
+                    .filter_map(|line| line.split_whitespace().next())
+                    .map(|c| c.to_string())
+                    .collect();
+                Ok((None, crates))
+            },
+            _ => Err(format!("unknown format \"{}\"", format)),
+        }
+    }
+
+    /// Removes the duplicated crates from a list, or from all the lists,
+    /// keeping the first occurrence of each crate
+    pub fn dedupe(list: Option<&str>) {
+        let _lock = Settings::lock();
+        let names: Vec<String> = match list {
+            Some(l) => {
+                if !Self::exists(l) {
+                    println!("List \"{}\" doesn't exist.", l.red());
+                    return;
+                }
+                vec![l.to_string()]
+            },
+            None => Self::names(),
+        };
+
+        for name in names {
+            if let Some(crates) = Self::crates(&name) {
+                let mut unique: Vec<String> = Vec::new();
+                for c in crates.iter() {
+                    if !unique.contains(c) {
+                        unique.push(c.to_string());
                     }
                 }
-                println!("Your lists:\n{}", lists_str[1..].trim());
-            } else {
-                println!("{}", err_msg);
+                let removed = crates.len() - unique.len();
+                if removed > 0 {
+                    println!("Removed {} duplicated crates from the list \"{}\".",
+                        removed.to_string().cyan(), name.bright_green());
+                    Self::set(&name, &unique);
+                } else {
+                    println!("The list \"{}\" has no duplicated crates.", name.green());
+                }
             }
-            
-        } else {
-            println!("{}", err_msg);
         }
     }
 
+    /// Returns the names of the valid lists
+    pub fn names() -> Vec<String> {
+        Self::typed().into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Show the saved lists
+    pub fn show_lists(recursive: bool) {
+        let lists = Self::typed();
+
+        if lists.is_empty() {
+            println!("You have no lists. Create a new one with '{}'",
+                "crin list new <listname>".bright_blue());
+            return;
+        }
+
+        let lists_str = if recursive {
+            // show also the contained crates
+            lists.iter().map(|(name, crates)| format!("{} {}: {}",
+                name.bright_green(),
+                format!("({})", crates.len()).cyan(),
+                crates.iter().map(|c| c.green().to_string()).collect::<Vec<_>>().join(", ")
+            )).collect::<Vec<_>>().join("\n")
+        } else {
+            // show just the lists with their number of crates
+            lists.iter().map(|(name, crates)| format!("{} {}",
+                name.bright_green(),
+                format!("({})", crates.len()).cyan()
+            )).collect::<Vec<_>>().join(", ")
+        };
+        println!("Your lists:\n{}", lists_str);
+    }
+
     /// Returns a string identifying the type of a TOML Value
-    fn typeof_value(value: Option<&Value>) -> &str {
+    pub fn typeof_value(value: Option<&Value>) -> &'static str {
         if let Some(v) = value {
             if v.is_integer() { return "an Integer"; }
             if v.is_str() { return "a String"; }
@@ -270,3 +601,88 @@ impl Lists {
         "<unknown>"
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"output = "text"
+
+[health]
+work = 1
+
+[lists]
+# the crates for work
+work = ["serde", "clap"] # pinned
+"my list" = ["rand"]
+
+# the last one
+misc = [
+    "log",
+]
+"#;
+
+    #[test]
+    fn rename_key_only_changes_the_key() {
+        let renamed = Lists::rename_key(CONFIG, "work", "job").unwrap();
+        assert_eq!(renamed, CONFIG.replace("\nwork = [", "\njob = ["));
+
+        let renamed = Lists::rename_key(CONFIG, "my list", "other").unwrap();
+        assert_eq!(renamed, CONFIG.replace("\"my list\" =", "other ="));
+
+        let renamed = Lists::rename_key(CONFIG, "misc", "new list").unwrap();
+        assert_eq!(renamed, CONFIG.replace("misc = [", "\"new list\" = ["));
+    }
+
+    #[test]
+    fn rename_key_not_found() {
+        assert_eq!(Lists::rename_key(CONFIG, "wor", "job"), None);
+        assert_eq!(Lists::rename_key(CONFIG, "log", "job"), None);
+        assert_eq!(Lists::rename_key("lists = { work = [] }\n", "work", "job"), None);
+    }
+
+    #[test]
+    fn rename_keeps_the_comments_of_the_lists() {
+        let before = CONFIG.parse::<Document>().unwrap();
+        let after = Lists::rename_key(CONFIG, "work", "job").unwrap().parse::<Document>().unwrap();
+        assert!(Lists::lists_renamed(&before, &after, "work", "job"));
+        assert!(!Lists::lists_renamed(&before, &before, "work", "job"));
+
+        let text = after.to_string();
+        assert!(text.contains("# the crates for work\njob = [\"serde\", \"clap\"] # pinned\n"));
+        assert!(text.contains("\n# the last one\nmisc = [\n"));
+    }
+
+    fn names(crates: &[&str]) -> Vec<String> {
+        crates.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_import_json() {
+        assert_eq!(Lists::parse_import(r#"{"name": "team", "crates": ["serde", "clap"]}"#, "json"),
+            Ok((Some("team".to_string()), names(&["serde", "clap"]))));
+        assert_eq!(Lists::parse_import(r#"["serde"]"#, "json"), Ok((None, names(&["serde"]))));
+        assert!(Lists::parse_import(r#"{"name": "team"}"#, "json").is_err());
+        assert!(Lists::parse_import(r#"["serde", "serde json"]"#, "json").is_err());
+    }
+
+    #[test]
+    fn parse_import_txt() {
+        assert_eq!(Lists::parse_import("serde\n# a comment\n\nclap # the cli\n", "txt"),
+            Ok((None, names(&["serde", "clap"]))));
+
+        // e.g. a Cargo.lock, read as plain text
+        let lockfile = "[[package]]\nname = \"serde\"\nversion = \"1.0.0\"\n";
+        let err = Lists::parse_import(lockfile, "txt").unwrap_err();
+        assert!(err.contains("\"[[package]]\""));
+    }
+
+    #[test]
+    fn parse_import_cargo() {
+        let manifest = "[dependencies]\nserde = \"1\"\nlocal = { path = \"../local\" }\n\
+            [dev-dependencies]\nclap = \"2\"\n";
+        assert_eq!(Lists::parse_import(manifest, "cargo"), Ok((None, names(&["serde", "clap"]))));
+        assert!(Lists::parse_import(manifest, "yaml").is_err());
+    }
+}
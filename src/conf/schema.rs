@@ -0,0 +1,420 @@
+use crate::conf::*;
+use chrono::format::{Item as FormatItem, StrftimeItems};
+use std::fmt;
+use toml_edit::Array;
+
+lazy_static! {
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+}
+
+/// The known top level keys in the config file
+pub const KNOWN_KEYS: [&str; 9] = ["output", "theme", "per_page", "date_format", "cache",
+    "registry", "license", "health", "lists"];
+
+/// The default output format
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    Text,
+    Json,
+}
+
+/// The color theme
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Theme {
+    Default,
+    /// no colors
+    Plain,
+}
+
+/// The policy checked by `license-audit` (`[license]`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LicensePolicy {
+    /// the SPDX identifiers allowed. If empty, all the ones not denied are allowed (`allow`)
+    pub allow: Vec<String>,
+    /// the SPDX identifiers denied (`deny`)
+    pub deny: Vec<String>,
+    /// whether the licenses neither allowed nor denied pass (`allow_unknown`)
+    pub allow_unknown: bool,
+    /// whether the crates without a license pass (`allow_missing`)
+    pub allow_missing: bool,
+}
+
+/// The thresholds of the factors scored by `health` (`[health]`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthThresholds {
+    /// the days since the last update that still score full (`fresh_days`)
+    pub fresh_days: u64,
+    /// the days since the last update that score zero (`stale_days`)
+    pub stale_days: u64,
+    /// the releases in the last year that score full (`releases_per_year`)
+    pub releases_per_year: u64,
+    /// the ratio of yanked versions that scores zero (`max_yanked_ratio`)
+    pub max_yanked_ratio: f64,
+    /// the owners that score full (`min_owners`)
+    pub min_owners: u64,
+    /// the ratio of recent to total downloads that scores full (`recent_ratio`)
+    pub recent_ratio: f64,
+    /// the reverse dependencies that score full (`min_rdeps`)
+    pub min_rdeps: u64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> HealthThresholds {
+        HealthThresholds {
+            fresh_days: 90,
+            stale_days: 730,
+            releases_per_year: 4,
+            max_yanked_ratio: 0.5,
+            min_owners: 2,
+            recent_ratio: 0.1,
+            min_rdeps: 100,
+        }
+    }
+}
+
+/// The typed settings, with the defaults applied for the missing ones
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// the default output format of `downloads` and `list export` (`output`)
+    pub output: Output,
+    /// the color theme (`theme`)
+    pub theme: Theme,
+    /// the number of results per page, up to 100 (`per_page`)
+    pub per_page: u64,
+    /// the strftime format used to show dates (`date_format`)
+    pub date_format: String,
+    /// the seconds that the cached crate names are valid (`cache.names_ttl`)
+    pub names_ttl: u64,
+    /// the seconds that the cached registry responses are valid,
+    /// or 0 to not cache them (`cache.responses_ttl`)
+    pub responses_ttl: u64,
+    /// the url of the registry, without the trailing slash (`registry`)
+    pub registry: String,
+    /// the license policy (`[license]`)
+    pub license: LicensePolicy,
+    /// the health thresholds (`[health]`)
+    pub health: HealthThresholds,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            output: Output::Text,
+            theme: Theme::Default,
+            per_page: 100,
+            date_format: "%Y-%m-%d".to_string(),
+            names_ttl: 7 * 24 * 60 * 60,
+            responses_ttl: 5 * 60,
+            registry: "https://crates.io".to_string(),
+            license: LicensePolicy::default(),
+            health: HealthThresholds::default(),
+        }
+    }
+}
+
+/// An invalid setting
+#[derive(Debug)]
+pub struct ConfigError {
+    /// the dotted key path of the setting
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid setting {}: {}", self.key.cyan(), self.message)
+    }
+}
+
+impl Output {
+    pub fn as_str(&self) -> &'static str {
+        match self { Output::Text => "text", Output::Json => "json" }
+    }
+}
+
+impl Theme {
+    pub fn as_str(&self) -> &'static str {
+        match self { Theme::Default => "default", Theme::Plain => "plain" }
+    }
+}
+
+impl Config {
+
+    /// Returns a copy of the current settings
+    pub fn current() -> Config {
+        CONFIG.read().unwrap().clone()
+    }
+
+    /// Makes these the current settings, and applies the color theme
+    pub fn install(self) {
+        if self.theme == Theme::Plain {
+            colored::control::set_override(false);
+        }
+        *CONFIG.write().unwrap() = self;
+    }
+
+    /// Loads the typed settings from a document.
+    ///
+    /// The missing and invalid settings get their default values,
+    /// and an error is returned for each invalid one.
+    pub fn from_doc(doc: &Document) -> (Config, Vec<ConfigError>) {
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+
+        if let Some(v) = Self::field(doc, "output", &mut errors, |i| match i.as_str() {
+            Some("text") => Ok(Output::Text),
+            Some("json") => Ok(Output::Json),
+            _ => Err("expected \"text\" or \"json\""),
+        }) { config.output = v; }
+
+        if let Some(v) = Self::field(doc, "theme", &mut errors, |i| match i.as_str() {
+            Some("default") => Ok(Theme::Default),
+            Some("plain") => Ok(Theme::Plain),
+            _ => Err("expected \"default\" or \"plain\""),
+        }) { config.theme = v; }
+
+        if let Some(v) = Self::field(doc, "per_page", &mut errors, |i| match i.as_integer() {
+            Some(n) if n >= 1 && n <= 100 => Ok(n as u64),
+            _ => Err("expected an integer between 1 and 100"),
+        }) { config.per_page = v; }
+
+        if let Some(v) = Self::field(doc, "date_format", &mut errors, |i| match i.as_str() {
+            Some(f) if !StrftimeItems::new(f).any(|item| item == FormatItem::Error) => {
+                Ok(f.to_string())
+            },
+            _ => Err("expected a valid strftime format string"),
+        }) { config.date_format = v; }
+
+        let seconds = |i: &Item| match i.as_integer() {
+            Some(n) if n >= 0 => Ok(n as u64),
+            _ => Err("expected a positive number of seconds"),
+        };
+        if let Some(v) = Self::field(doc, "cache.names_ttl", &mut errors, seconds) {
+            config.names_ttl = v;
+        }
+        if let Some(v) = Self::field(doc, "cache.responses_ttl", &mut errors, seconds) {
+            config.responses_ttl = v;
+        }
+
+        if let Some(v) = Self::field(doc, "registry", &mut errors, |i| match i.as_str() {
+            Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+                Ok(url.trim_end_matches('/').to_string())
+            },
+            _ => Err("expected an http or https url"),
+        }) { config.registry = v; }
+
+        let strings = |i: &Item| i.as_array().and_then(|arr| {
+            arr.iter().map(|l| l.as_str().map(|l| l.to_string())).collect::<Option<Vec<_>>>()
+        }).ok_or("expected an array of SPDX license identifiers");
+
+        if let Some(v) = Self::field(doc, "license.allow", &mut errors, strings) {
+            config.license.allow = v;
+        }
+        if let Some(v) = Self::field(doc, "license.deny", &mut errors, strings) {
+            config.license.deny = v;
+        }
+        if let Some(v) = Self::field(doc, "license.allow_unknown", &mut errors, |i| {
+            i.as_bool().ok_or("expected true or false")
+        }) { config.license.allow_unknown = v; }
+
+        if let Some(v) = Self::field(doc, "license.allow_missing", &mut errors, |i| {
+            i.as_bool().ok_or("expected true or false")
+        }) { config.license.allow_missing = v; }
+
+        let count = |i: &Item| match i.as_integer() {
+            Some(n) if n >= 0 => Ok(n as u64),
+            _ => Err("expected a positive integer"),
+        };
+        // the factors divided by them, which can't be 0
+        let positive = |i: &Item| match i.as_integer() {
+            Some(n) if n >= 1 => Ok(n as u64),
+            _ => Err("expected an integer greater than 0"),
+        };
+        let ratio = |i: &Item| match i.as_float().or(i.as_integer().map(|n| n as f64)) {
+            Some(r) if r > 0.0 && r <= 1.0 => Ok(r),
+            _ => Err("expected a number greater than 0 and up to 1"),
+        };
+        let h = &mut config.health;
+        if let Some(v) = Self::field(doc, "health.fresh_days", &mut errors, count) {
+            h.fresh_days = v;
+        }
+        if let Some(v) = Self::field(doc, "health.stale_days", &mut errors, count) {
+            h.stale_days = v;
+        }
+        // checked once both are resolved, since either may be the default,
+        // and reported on the ones that are set, so that resetting them fixes it
+        if h.fresh_days >= h.stale_days {
+            if Settings::item(doc, "health.fresh_days").is_some() {
+                errors.push(ConfigError {
+                    key: "health.fresh_days".to_string(),
+                    message: format!("expected fewer days than health.stale_days ({}), found {}",
+                        h.stale_days, h.fresh_days),
+                });
+            }
+            if Settings::item(doc, "health.stale_days").is_some() {
+                errors.push(ConfigError {
+                    key: "health.stale_days".to_string(),
+                    message: format!("expected more days than health.fresh_days ({}), found {}",
+                        h.fresh_days, h.stale_days),
+                });
+            }
+            let defaults = HealthThresholds::default();
+            h.fresh_days = defaults.fresh_days;
+            h.stale_days = defaults.stale_days;
+        }
+        if let Some(v) = Self::field(doc, "health.releases_per_year", &mut errors, positive) {
+            h.releases_per_year = v;
+        }
+        if let Some(v) = Self::field(doc, "health.max_yanked_ratio", &mut errors, ratio) {
+            h.max_yanked_ratio = v;
+        }
+        if let Some(v) = Self::field(doc, "health.min_owners", &mut errors, positive) {
+            h.min_owners = v;
+        }
+        if let Some(v) = Self::field(doc, "health.recent_ratio", &mut errors, ratio) {
+            h.recent_ratio = v;
+        }
+        if let Some(v) = Self::field(doc, "health.min_rdeps", &mut errors, positive) {
+            h.min_rdeps = v;
+        }
+
+        // the lists are read by `Lists`, but they are validated here too
+        errors.extend(Self::lists_from(doc).1);
+
+        (config, errors)
+    }
+
+    /// Loads the valid lists of crates from a document, in order,
+    /// with an error for each invalid one
+    pub fn lists_from(doc: &Document) -> (Vec<(String, Vec<String>)>, Vec<ConfigError>) {
+        let mut valid = Vec::new();
+        let mut errors = Vec::new();
+
+        if let Some(lists) = Settings::item(doc, "lists") {
+            if let Some(table) = lists.as_table() {
+                for (name, list) in table.iter() {
+                    let crates = list.as_array().and_then(|arr| {
+                        arr.iter().map(|c| c.as_str().map(|c| c.to_string())).collect()
+                    });
+                    match crates {
+                        Some(crates) => valid.push((name.to_string(), crates)),
+                        None => errors.push(ConfigError {
+                            key: format!("lists.{}", name),
+                            message: format!("expected an array of crate names, found {}",
+                                Doctor::typeof_item(list)),
+                        }),
+                    }
+                }
+            } else {
+                errors.push(ConfigError {
+                    key: "lists".to_string(),
+                    message: format!("expected a table, found {}", Doctor::typeof_item(lists)),
+                });
+            }
+        }
+        (valid, errors)
+    }
+
+    /// Writes the settings back to a document, only changing the values
+    /// that differ, so that the rest of the document is left untouched.
+    ///
+    /// The lists are not written, since they are managed by `Lists`.
+    pub fn write_to(&self, doc: &mut Document) {
+        let (current, errors) = Self::from_doc(doc);
+        let invalid = |key: &str| errors.iter().any(|e| e.key == key);
+        let mut changes: Vec<(&str, Value)> = Vec::new();
+
+        if current.output != self.output || invalid("output") {
+            changes.push(("output", Value::from(self.output.as_str())));
+        }
+        if current.theme != self.theme || invalid("theme") {
+            changes.push(("theme", Value::from(self.theme.as_str())));
+        }
+        if current.per_page != self.per_page || invalid("per_page") {
+            changes.push(("per_page", Value::from(self.per_page as i64)));
+        }
+        if current.date_format != self.date_format || invalid("date_format") {
+            changes.push(("date_format", Value::from(self.date_format.as_str())));
+        }
+        if current.names_ttl != self.names_ttl || invalid("cache.names_ttl") {
+            changes.push(("cache.names_ttl", Value::from(self.names_ttl as i64)));
+        }
+        if current.responses_ttl != self.responses_ttl || invalid("cache.responses_ttl") {
+            changes.push(("cache.responses_ttl", Value::from(self.responses_ttl as i64)));
+        }
+        if current.registry != self.registry || invalid("registry") {
+            changes.push(("registry", Value::from(self.registry.as_str())));
+        }
+
+        let array = |ids: &[String]| {
+            let mut arr = Array::default();
+            for id in ids { arr.push(id.as_str()); }
+            Value::from(arr)
+        };
+        if current.license.allow != self.license.allow || invalid("license.allow") {
+            changes.push(("license.allow", array(&self.license.allow)));
+        }
+        if current.license.deny != self.license.deny || invalid("license.deny") {
+            changes.push(("license.deny", array(&self.license.deny)));
+        }
+        if current.license.allow_unknown != self.license.allow_unknown
+            || invalid("license.allow_unknown") {
+            changes.push(("license.allow_unknown", Value::from(self.license.allow_unknown)));
+        }
+        if current.license.allow_missing != self.license.allow_missing
+            || invalid("license.allow_missing") {
+            changes.push(("license.allow_missing", Value::from(self.license.allow_missing)));
+        }
+
+        let (h, ch) = (&self.health, &current.health);
+        let counts = [
+            ("health.fresh_days", h.fresh_days, ch.fresh_days),
+            ("health.stale_days", h.stale_days, ch.stale_days),
+            ("health.releases_per_year", h.releases_per_year, ch.releases_per_year),
+            ("health.min_owners", h.min_owners, ch.min_owners),
+            ("health.min_rdeps", h.min_rdeps, ch.min_rdeps),
+        ];
+        for (key, new, old) in counts.iter() {
+            if new != old || invalid(key) {
+                changes.push((*key, Value::from(*new as i64)));
+            }
+        }
+        let ratios = [
+            ("health.max_yanked_ratio", h.max_yanked_ratio, ch.max_yanked_ratio),
+            ("health.recent_ratio", h.recent_ratio, ch.recent_ratio),
+        ];
+        for (key, new, old) in ratios.iter() {
+            if new != old || invalid(key) {
+                changes.push((*key, Value::from(*new)));
+            }
+        }
+
+        for (key, v) in changes {
+            if let Err(e) = Settings::set_value(doc, key, v) {
+                println!("Error: {}", e);
+            }
+        }
+    }
+
+    /// Reads and parses a setting, returning None when it's missing,
+    /// or when it's invalid, in which case an error is recorded
+    fn field<T, F>(doc: &Document, key: &str, errors: &mut Vec<ConfigError>, parse: F)
+        -> Option<T> where F: Fn(&Item) -> Result<T, &'static str> {
+
+        let item = Settings::item(doc, key)?;
+        match parse(item) {
+            Ok(v) => Some(v),
+            Err(expected) => {
+                let found = match item.as_value() {
+                    Some(v) => v.to_string().trim().to_string(),
+                    None => Doctor::typeof_item(item).to_string(),
+                };
+                errors.push(ConfigError {
+                    key: key.to_string(),
+                    message: format!("{}, found {}", expected, found),
+                });
+                None
+            }
+        }
+    }
+}
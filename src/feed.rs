@@ -0,0 +1,99 @@
+use super::{APPNAME, VERSION};
+
+use chrono::{DateTime, Utc};
+
+use crate::util::date_rfc3339;
+
+/// An entry of a feed
+pub struct Entry {
+    pub title: String,
+    /// the url of the entry, also used as its unique id
+    pub link: String,
+    pub date: DateTime<Utc>,
+    pub summary: Option<String>,
+}
+
+/// Generates an Atom feed, identified by `id`.
+///
+/// The entries are sorted from the newest, and the date of the feed is the one
+/// of its newest entry, so that the same data always generates the same feed.
+pub fn atom(title: &str, link: &str, id: &str, mut entries: Vec<Entry>) -> String {
+    sort(&mut entries);
+    let updated = entries.first().map(|e| e.date).unwrap_or_else(epoch);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape(title)));
+    xml.push_str(&format!("  <link href=\"{}\"/>\n", escape(link)));
+    xml.push_str(&format!("  <id>{}</id>\n", escape(id)));
+    xml.push_str(&format!("  <updated>{}</updated>\n", date_rfc3339(&updated)));
+    // required at the feed level, since the entries don't have their own
+    xml.push_str(&format!("  <author><name>{}</name></author>\n", APPNAME));
+    xml.push_str(&format!("  <generator version=\"{}\">{}</generator>\n", VERSION, APPNAME));
+
+    for e in entries.iter() {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape(&e.title)));
+        xml.push_str(&format!("    <link href=\"{}\"/>\n", escape(&e.link)));
+        xml.push_str(&format!("    <id>{}</id>\n", escape(&e.link)));
+        xml.push_str(&format!("    <published>{}</published>\n", date_rfc3339(&e.date)));
+        xml.push_str(&format!("    <updated>{}</updated>\n", date_rfc3339(&e.date)));
+        if let Some(ref summary) = e.summary {
+            xml.push_str(&format!("    <summary>{}</summary>\n", escape(summary)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Generates an RSS 2.0 feed, sorted and dated like the Atom one
+pub fn rss(title: &str, link: &str, mut entries: Vec<Entry>) -> String {
+    sort(&mut entries);
+    let updated = entries.first().map(|e| e.date).unwrap_or_else(epoch);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape(title)));
+    xml.push_str(&format!("  <link>{}</link>\n", escape(link)));
+    xml.push_str(&format!("  <description>{}</description>\n", escape(title)));
+    xml.push_str(&format!("  <lastBuildDate>{}</lastBuildDate>\n", updated.to_rfc2822()));
+    xml.push_str(&format!("  <generator>{} {}</generator>\n", APPNAME, VERSION));
+
+    for e in entries.iter() {
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape(&e.title)));
+        xml.push_str(&format!("    <link>{}</link>\n", escape(&e.link)));
+        xml.push_str(&format!("    <guid isPermaLink=\"true\">{}</guid>\n", escape(&e.link)));
+        xml.push_str(&format!("    <pubDate>{}</pubDate>\n", e.date.to_rfc2822()));
+        if let Some(ref summary) = e.summary {
+            xml.push_str(&format!("    <description>{}</description>\n", escape(summary)));
+        }
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+/// Sorts the entries from the newest, and then by title and link
+fn sort(entries: &mut Vec<Entry>) {
+    entries.sort_by(|a, b| b.date.cmp(&a.date)
+        .then(a.title.cmp(&b.title))
+        .then(a.link.cmp(&b.link)));
+}
+
+/// The date of an empty feed
+fn epoch() -> DateTime<Utc> {
+    DateTime::from_utc(chrono::NaiveDateTime::from_timestamp(0, 0), Utc)
+}
+
+/// Escapes the text for XML, removing the control characters it doesn't allow
+fn escape(text: &str) -> String {
+    text.chars().filter(|&c| c >= ' ' || c == '\t' || c == '\n' || c == '\r')
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
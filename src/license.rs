@@ -0,0 +1,292 @@
+use crate::conf::LicensePolicy;
+
+/// A parsed SPDX license expression, e.g. `MIT OR Apache-2.0`
+#[derive(Debug, PartialEq)]
+pub enum License {
+    /// a license identifier, e.g. `MIT` or `GPL-2.0+`
+    Id(String),
+    /// a license with an exception, e.g. `GPL-2.0 WITH Classpath-exception-2.0`
+    With(String, String),
+    And(Box<License>, Box<License>),
+    Or(Box<License>, Box<License>),
+}
+
+/// The result of checking a license against the policy,
+/// ordered from the worst to the best
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Denied,
+    Unknown,
+    Allowed,
+}
+
+impl License {
+
+    /// Parses an SPDX license expression.
+    ///
+    /// The operators are accepted in any case, and the legacy `/` separator
+    /// used by many crates (e.g. `MIT/Apache-2.0`) is read as `OR`.
+    pub fn parse(expr: &str) -> Result<License, String> {
+        let tokens = tokenize(expr);
+        let mut pos = 0;
+        let license = parse_or(&tokens, &mut pos)?;
+        match tokens.get(pos) {
+            None => Ok(license),
+            Some(t) => Err(format!("unexpected \"{}\"", t)),
+        }
+    }
+
+    /// Checks the license against the policy. An `OR` is as good as its best
+    /// choice, and an `AND` is as bad as its worst part.
+    pub fn check(&self, policy: &LicensePolicy) -> Verdict {
+        match self {
+            License::Id(id) => check_id(id, policy),
+            License::With(id, exception) => {
+                // a policy may list the exact combination, or else it's checked as
+                // the license, so that an exception can't get it past the deny list
+                let full = format!("{} WITH {}", id, exception);
+                if listed(&full, &policy.deny) {
+                    Verdict::Denied
+                } else if listed(&full, &policy.allow) {
+                    Verdict::Allowed
+                } else {
+                    check_id(id, policy)
+                }
+            },
+            License::And(a, b) => a.check(policy).min(b.check(policy)),
+            License::Or(a, b) => a.check(policy).max(b.check(policy)),
+        }
+    }
+}
+
+/// Checks a license identifier against the policy, ignoring the case
+/// and the version suffixes, so that `GPL-3.0` also matches `GPL-3.0-only`,
+/// `GPL-3.0-or-later` and `GPL-3.0+`
+fn check_id(id: &str, policy: &LicensePolicy) -> Verdict {
+    if listed(id, &policy.deny) {
+        Verdict::Denied
+    } else if listed(id, &policy.allow) || policy.allow.is_empty() {
+        Verdict::Allowed
+    } else {
+        Verdict::Unknown
+    }
+}
+
+/// Returns true if a license identifier is in a list of the policy,
+/// comparing them like `check_id`
+fn listed(id: &str, ids: &[String]) -> bool {
+    let id = base_id(id);
+    ids.iter().any(|i| base_id(i) == id)
+}
+
+/// Returns a license identifier in lowercase, without the version suffixes
+/// (`+`, `-only` and `-or-later`) of the license, even if it has an exception
+fn base_id(id: &str) -> String {
+    let id = id.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let mut parts = id.splitn(2, " with ");
+    let license = parts.next().unwrap_or("").trim_end_matches('+')
+        .trim_end_matches("-only").trim_end_matches("-or-later");
+
+    match parts.next() {
+        Some(exception) => format!("{} with {}", license, exception),
+        None => license.to_string(),
+    }
+}
+
+/// Splits an expression into parenthesis, operators and identifiers
+fn tokenize(expr: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+
+    for c in expr.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' || c == '/' {
+            if !current.is_empty() {
+                tokens.push(current.split_off(0));
+            }
+            match c {
+                '/' => tokens.push("OR".to_string()),
+                '(' | ')' => tokens.push(c.to_string()),
+                _ => (),
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Returns true if the token at the position is the operator
+fn is_op(tokens: &[String], pos: usize, op: &str) -> bool {
+    tokens.get(pos).map_or(false, |t| t.eq_ignore_ascii_case(op))
+}
+
+fn parse_or(tokens: &[String], pos: &mut usize) -> Result<License, String> {
+    let mut license = parse_and(tokens, pos)?;
+    while is_op(tokens, *pos, "OR") {
+        *pos += 1;
+        license = License::Or(Box::new(license), Box::new(parse_and(tokens, pos)?));
+    }
+    Ok(license)
+}
+
+fn parse_and(tokens: &[String], pos: &mut usize) -> Result<License, String> {
+    let mut license = parse_with(tokens, pos)?;
+    while is_op(tokens, *pos, "AND") {
+        *pos += 1;
+        license = License::And(Box::new(license), Box::new(parse_with(tokens, pos)?));
+    }
+    Ok(license)
+}
+
+fn parse_with(tokens: &[String], pos: &mut usize) -> Result<License, String> {
+    let license = parse_primary(tokens, pos)?;
+    if is_op(tokens, *pos, "WITH") {
+        *pos += 1;
+        let exception = parse_id(tokens, pos)?;
+        match license {
+            License::Id(id) => Ok(License::With(id, exception)),
+            _ => Err("WITH must follow a license identifier".to_string()),
+        }
+    } else {
+        Ok(license)
+    }
+}
+
+fn parse_primary(tokens: &[String], pos: &mut usize) -> Result<License, String> {
+    if tokens.get(*pos).map(String::as_str) == Some("(") {
+        *pos += 1;
+        let license = parse_or(tokens, pos)?;
+        if tokens.get(*pos).map(String::as_str) != Some(")") {
+            return Err("missing \")\"".to_string());
+        }
+        *pos += 1;
+        Ok(license)
+    } else {
+        Ok(License::Id(parse_id(tokens, pos)?))
+    }
+}
+
+fn parse_id(tokens: &[String], pos: &mut usize) -> Result<String, String> {
+    match tokens.get(*pos) {
+        Some(t) if t != "(" && t != ")" && !["AND", "OR", "WITH"].iter()
+            .any(|op| t.eq_ignore_ascii_case(op)) => {
+            *pos += 1;
+            Ok(t.clone())
+        },
+        Some(t) => Err(format!("expected a license identifier, found \"{}\"", t)),
+        None => Err("expected a license identifier".to_string()),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(id: &str) -> Box<License> {
+        Box::new(License::Id(id.to_string()))
+    }
+
+    fn policy(allow: &[&str], deny: &[&str]) -> LicensePolicy {
+        LicensePolicy {
+            allow: allow.iter().map(|l| l.to_string()).collect(),
+            deny: deny.iter().map(|l| l.to_string()).collect(),
+            ..LicensePolicy::default()
+        }
+    }
+
+    fn check(expr: &str, policy: &LicensePolicy) -> Verdict {
+        License::parse(expr).unwrap().check(policy)
+    }
+
+    #[test]
+    fn parse_operators() {
+        assert_eq!(License::parse("MIT").unwrap(), *id("MIT"));
+        assert_eq!(License::parse("MIT OR Apache-2.0").unwrap(),
+            License::Or(id("MIT"), id("Apache-2.0")));
+        assert_eq!(License::parse("MIT and Zlib").unwrap(),
+            License::And(id("MIT"), id("Zlib")));
+        assert_eq!(License::parse("GPL-2.0 WITH Classpath-exception-2.0").unwrap(),
+            License::With("GPL-2.0".to_string(), "Classpath-exception-2.0".to_string()));
+    }
+
+    #[test]
+    fn parse_precedence_and_parentheses() {
+        // AND binds tighter than OR
+        assert_eq!(License::parse("MIT OR Apache-2.0 AND Zlib").unwrap(),
+            License::Or(id("MIT"), Box::new(License::And(id("Apache-2.0"), id("Zlib")))));
+        assert_eq!(License::parse("(MIT OR Apache-2.0) AND Zlib").unwrap(),
+            License::And(Box::new(License::Or(id("MIT"), id("Apache-2.0"))), id("Zlib")));
+    }
+
+    #[test]
+    fn parse_legacy_slashes() {
+        assert_eq!(License::parse("MIT/Apache-2.0").unwrap(),
+            License::Or(id("MIT"), id("Apache-2.0")));
+        assert_eq!(License::parse("MIT / Apache-2.0").unwrap(),
+            License::Or(id("MIT"), id("Apache-2.0")));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(License::parse("").is_err());
+        assert!(License::parse("MIT OR").is_err());
+        assert!(License::parse("(MIT").is_err());
+        assert!(License::parse("MIT)").is_err());
+        assert!(License::parse("(MIT OR Zlib) WITH LLVM-exception").is_err());
+    }
+
+    #[test]
+    fn check_or_and() {
+        let p = policy(&["MIT"], &["GPL-3.0"]);
+        assert_eq!(check("MIT OR GPL-3.0", &p), Verdict::Allowed);
+        assert_eq!(check("MIT AND GPL-3.0", &p), Verdict::Denied);
+        assert_eq!(check("MIT AND Zlib", &p), Verdict::Unknown);
+        assert_eq!(check("Zlib OR GPL-3.0", &p), Verdict::Unknown);
+        assert_eq!(check("(MIT OR GPL-3.0) AND (MIT OR Zlib)", &p), Verdict::Allowed);
+    }
+
+    #[test]
+    fn check_empty_allow() {
+        let p = policy(&[], &["GPL-3.0"]);
+        assert_eq!(check("Zlib", &p), Verdict::Allowed);
+        assert_eq!(check("GPL-3.0", &p), Verdict::Denied);
+    }
+
+    #[test]
+    fn check_suffixes() {
+        let p = policy(&["Apache-2.0"], &["GPL-3.0"]);
+        assert_eq!(check("GPL-3.0-only", &p), Verdict::Denied);
+        assert_eq!(check("GPL-3.0-or-later", &p), Verdict::Denied);
+        assert_eq!(check("GPL-3.0+", &p), Verdict::Denied);
+        assert_eq!(check("gpl-3.0", &p), Verdict::Denied);
+        assert_eq!(check("MIT/GPL-3.0-or-later", &p), Verdict::Unknown);
+
+        let p = policy(&["GPL-3.0-or-later"], &[]);
+        assert_eq!(check("GPL-3.0", &p), Verdict::Allowed);
+    }
+
+    #[test]
+    fn check_with() {
+        let p = policy(&["GPL-2.0 WITH Classpath-exception-2.0"], &["GPL-2.0"]);
+        assert_eq!(check("GPL-2.0 WITH Classpath-exception-2.0", &p), Verdict::Allowed);
+        assert_eq!(check("GPL-2.0-only WITH Classpath-exception-2.0", &p), Verdict::Allowed);
+        assert_eq!(check("GPL-2.0 WITH LLVM-exception", &p), Verdict::Denied);
+        assert_eq!(check("GPL-2.0", &p), Verdict::Denied);
+    }
+
+    #[test]
+    fn check_with_empty_allow() {
+        let p = policy(&[], &["GPL-2.0"]);
+        assert_eq!(check("GPL-2.0 WITH Classpath-exception-2.0", &p), Verdict::Denied);
+        assert_eq!(check("GPL-2.0-or-later WITH Classpath-exception-2.0", &p), Verdict::Denied);
+        assert_eq!(check("Apache-2.0 WITH LLVM-exception", &p), Verdict::Allowed);
+
+        let p = policy(&[], &["GPL-2.0 WITH Classpath-exception-2.0"]);
+        assert_eq!(check("GPL-2.0 WITH Classpath-exception-2.0", &p), Verdict::Denied);
+        assert_eq!(check("GPL-2.0", &p), Verdict::Allowed);
+    }
+}
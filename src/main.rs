@@ -11,13 +11,24 @@ const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 const ORGANIZATION: &'static str = "joseluis";
 const APPNAME: &'static str = env!("CARGO_PKG_NAME");
 const CONFIGNAME: &'static str = "config.toml";
+const LOCALCONFIGNAME: &'static str = ".crin.toml";
 
 mod args;
 mod conf;
 mod actions;
+mod cache;
+mod feed;
+mod license;
+mod manifest;
+mod picker;
+mod readme;
+mod registry;
+mod tui;
 mod util;
+mod watch;
 
 fn main() {
-    conf::Settings::read();
-    args::CliArguments::new().parse();
+    let cli = args::CliArguments::new();
+    conf::Settings::init(cli.config());
+    cli.parse();
 }
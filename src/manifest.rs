@@ -0,0 +1,113 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{Document, Item};
+
+/// A dependency declared in a Cargo.toml manifest or a Cargo.lock file
+pub struct Dependency {
+    /// the name of the crate in the registry (resolving renamed dependencies)
+    pub name: String,
+    /// the version requirement in a manifest, or the locked version in a lockfile
+    pub version: Option<String>,
+    /// either "normal", "dev" or "build"
+    pub kind: &'static str,
+}
+
+/// The dependency sections of a manifest, with their kind
+const SECTIONS: [(&str, &str); 3] = [
+    ("dependencies", "normal"),
+    ("dev-dependencies", "dev"),
+    ("build-dependencies", "build"),
+];
+
+/// Returns the path to the Cargo.toml file when provided with a directory
+pub fn resolve(path: &Path) -> PathBuf {
+    if path.is_dir() { path.join("Cargo.toml") } else { path.to_owned() }
+}
+
+/// Returns true if the path points to a Cargo.lock file
+pub fn is_lockfile(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some("lock")
+}
+
+/// Reads the dependencies from either a manifest or a lockfile.
+///
+/// The normal dependencies of a manifest are always included, while the `dev`
+/// and `build` ones only when their kind is included. A lockfile includes
+/// the packages from a registry, but not the local, workspace or git ones.
+pub fn dependencies(path: &Path, include: &[&str]) -> Result<Vec<Dependency>, String> {
+    let path = resolve(path);
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("couldn't read \"{}\": {}", path.display(), e))?;
+    let doc = text.parse::<Document>()
+        .map_err(|e| format!("couldn't parse \"{}\": {}", path.display(), e))?;
+
+    if is_lockfile(&path) {
+        Ok(lockfile_dependencies(&doc))
+    } else {
+        Ok(manifest_dependencies(&doc, include))
+    }
+}
+
+/// Returns the dependencies declared in a manifest, including the
+/// platform specific ones under `[target.'cfg(...)'.dependencies]`
+pub fn manifest_dependencies(doc: &Document, include: &[&str]) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+
+    for (section, kind) in SECTIONS.iter() {
+        if *kind != "normal" && !include.contains(kind) { continue; }
+
+        push_section(&mut deps, &doc[*section], *kind);
+
+        if let Some(targets) = doc["target"].as_table() {
+            for (_cfg, target) in targets.iter() {
+                push_section(&mut deps, &target[*section], *kind);
+            }
+        }
+    }
+    deps
+}
+
+/// Returns the packages from a registry contained in a lockfile
+pub fn lockfile_dependencies(doc: &Document) -> Vec<Dependency> {
+    let mut deps = Vec::new();
+
+    if let Some(packages) = doc["package"].as_array_of_tables() {
+        for package in packages.iter() {
+            // local and workspace packages have no source, and the git ones
+            // (`git+https://…`) may not even be in the registry under that name
+            let from_registry = package["source"].as_str()
+                .map_or(false, |s| s.starts_with("registry+"));
+            if !from_registry { continue; }
+
+            if let Some(name) = package["name"].as_str() {
+                deps.push(Dependency {
+                    name: name.to_string(),
+                    version: package["version"].as_str().map(|v| v.to_string()),
+                    kind: "normal",
+                });
+            }
+        }
+    }
+    deps
+}
+
+/// Adds the dependencies contained in a manifest section
+fn push_section(deps: &mut Vec<Dependency>, section: &Item, kind: &'static str) {
+    if let Some(table) = section.as_table() {
+        for (key, dep) in table.iter() {
+            // path & git only dependencies are not in the registry
+            if dep["path"].as_str().is_some() || dep["git"].as_str().is_some() { continue; }
+
+            // renamed dependencies: `alias = { package = "name", ... }`
+            let name = dep["package"].as_str().unwrap_or(key);
+            let version = dep.as_str().or_else(|| dep["version"].as_str());
+
+            deps.push(Dependency {
+                name: name.to_string(),
+                version: version.map(|v| v.to_string()),
+                kind,
+            });
+        }
+    }
+}
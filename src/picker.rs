@@ -0,0 +1,148 @@
+use std::io::{self, Stdout, Write};
+
+use crossterm::{queue, Result};
+use crossterm::cursor::{MoveToColumn, MoveUp};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::tty::IsTty;
+
+/// The maximum number of matches shown
+const SHOWN: usize = 10;
+
+/// Returns true if both stdin and stdout are an interactive terminal
+pub fn is_interactive() -> bool {
+    io::stdin().is_tty() && io::stdout().is_tty()
+}
+
+/// Asks to choose one of the candidates with a fuzzy finder,
+/// returning None if cancelled or if the terminal is not interactive.
+///
+/// If `allow_new` is true, a text that doesn't match any candidate can also be chosen.
+pub fn pick(prompt: &str, candidates: &[String], allow_new: bool) -> Option<String> {
+    if !is_interactive() || (candidates.is_empty() && !allow_new) {
+        return None;
+    }
+    let mut out = io::stdout();
+
+    terminal::enable_raw_mode().ok()?;
+    let res = pick_loop(&mut out, prompt, candidates, allow_new);
+    let _ = queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown));
+    let _ = out.flush();
+    let _ = terminal::disable_raw_mode();
+
+    res.ok().and_then(|choice| choice)
+}
+
+fn pick_loop(out: &mut Stdout, prompt: &str, candidates: &[String], allow_new: bool)
+    -> Result<Option<String>> {
+
+    let mut query = String::new();
+    let mut selected = 0;
+
+    loop {
+        let matches = rank(&query, candidates);
+        selected = selected.min(matches.len().saturating_sub(1));
+        draw(out, prompt, &query, &matches, selected)?;
+
+        if let Event::Key(key) = event::read()? {
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Enter => {
+                    if let Some(choice) = matches.get(selected) {
+                        return Ok(Some(choice.to_string()));
+                    } else if allow_new && !query.trim().is_empty() {
+                        return Ok(Some(query.trim().to_string()));
+                    }
+                },
+                KeyCode::Up => selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Tab => selected += 1,
+                KeyCode::Backspace => { query.pop(); selected = 0; },
+                KeyCode::Char(c) if !ctrl => { query.push(c); selected = 0; },
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Draws the prompt line and the best matches below it,
+/// leaving the cursor at the end of the prompt line
+fn draw(out: &mut Stdout, prompt: &str, query: &str, matches: &[&str], selected: usize)
+    -> Result<()> {
+
+    let width = terminal::size().map(|s| s.0 as usize).unwrap_or(80);
+    let shown = matches.len().min(SHOWN);
+
+    queue!(out, MoveToColumn(0), Clear(ClearType::FromCursorDown),
+        SetForegroundColor(Color::Blue), Print(prompt), ResetColor,
+        Print(" "), Print(query))?;
+
+    for (i, name) in matches.iter().take(shown).enumerate() {
+        let line: String = name.chars().take(width.saturating_sub(3)).collect();
+        queue!(out, Print("\r\n"))?;
+        if i == selected {
+            queue!(out, SetForegroundColor(Color::Green), SetAttribute(Attribute::Reverse),
+                Print(format!("> {}", line)), SetAttribute(Attribute::Reset), ResetColor)?;
+        } else {
+            queue!(out, Print(format!("  {}", line)))?;
+        }
+    }
+    if matches.len() > shown {
+        queue!(out, Print("\r\n"), SetForegroundColor(Color::DarkGrey),
+            Print(format!("  ({} more)", matches.len() - shown)), ResetColor)?;
+    }
+
+    let below = shown + (matches.len() > shown) as usize;
+    if below > 0 {
+        queue!(out, MoveUp(below as u16))?;
+    }
+    let column = prompt.chars().count() + 1 + query.chars().count();
+    queue!(out, MoveToColumn(column as u16))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Returns the candidates matching the query, best first
+fn rank<'a>(query: &str, candidates: &'a [String]) -> Vec<&'a str> {
+    let mut scored: Vec<(i64, &str)> = candidates.iter()
+        .filter_map(|c| fuzzy_score(query, c).map(|score| (score, c.as_str())))
+        .collect();
+    // the best score first, then the shortest, then alphabetically
+    scored.sort_by(|a, b| b.0.cmp(&a.0)
+        .then(a.1.len().cmp(&b.1.len()))
+        .then(a.1.cmp(b.1)));
+    scored.into_iter().map(|s| s.1).collect()
+}
+
+/// Scores how well a text matches a query whose characters appear in it
+/// in the same order, ignoring the case. Returns None if it doesn't match.
+///
+/// Matches at the start of the text or of a word, and consecutive matches,
+/// score higher. Gaps between the matched characters score lower.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut last: Option<usize> = None;
+    let mut pos = 0;
+
+    for q in query.to_lowercase().chars() {
+        let found = pos + text[pos..].iter().position(|&t| t == q)?;
+
+        if found == 0 {
+            score += 8;
+        } else if text[found - 1] == '-' || text[found - 1] == '_' {
+            score += 6;
+        }
+        match last {
+            Some(l) if l + 1 == found => score += 5,
+            Some(l) => score -= (found - l - 1).min(5) as i64,
+            None => score -= found.min(5) as i64,
+        }
+        score += 1;
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
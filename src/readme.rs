@@ -0,0 +1,332 @@
+use colored::*;
+
+/// Renders the HTML of a README as styled terminal text:
+/// headings, paragraphs, lists, quotes, code blocks, links and tables
+pub fn render(html: &str) -> String {
+    let mut r = Renderer::default();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+        } else if rest.starts_with('<') {
+            match rest.find('>') {
+                Some(end) => {
+                    r.tag(&rest[1..end]);
+                    rest = &rest[end + 1..];
+                },
+                None => {
+                    r.text(rest);
+                    rest = "";
+                }
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            r.text(&decode(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+    format!("{}\n", r.out.trim_end())
+}
+
+#[derive(Default)]
+struct Renderer {
+    out: String,
+    /// true at the start of a line, before its indentation
+    line_start: bool,
+    /// true when a space is due before the next word
+    space: bool,
+    /// inside a preformatted block
+    pre: bool,
+    /// the open lists, with the next number of the ordered ones
+    lists: Vec<Option<usize>>,
+    /// the bullet of a list item not written yet
+    bullet: Option<String>,
+    quotes: usize,
+    heading: Option<usize>,
+    bold: usize,
+    italic: usize,
+    code: usize,
+    /// the open links, with where their text starts in `out`
+    links: Vec<(String, usize)>,
+    /// the depth inside elements whose content is not shown, like scripts
+    skip: usize,
+    /// the cells already written in the current table row
+    cells: usize,
+}
+
+impl Renderer {
+
+    fn tag(&mut self, tag: &str) {
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name: String = tag.chars()
+            .take_while(|c| !c.is_whitespace() && *c != '/')
+            .collect::<String>().to_lowercase();
+
+        if ["script", "style", "svg", "template"].contains(&name.as_str()) {
+            if closing { self.skip = self.skip.saturating_sub(1); } else if !tag.ends_with('/') {
+                self.skip += 1;
+            }
+            return;
+        }
+        if self.skip > 0 {
+            return;
+        }
+
+        match (name.as_str(), closing) {
+            ("h1", false) | ("h2", false) | ("h3", false) |
+            ("h4", false) | ("h5", false) | ("h6", false) => {
+                self.blank_line();
+                let level = name[1..].parse().unwrap_or(1);
+                self.heading = Some(level);
+                self.write(&format!("{} ", "#".repeat(level)));
+            },
+            ("h1", true) | ("h2", true) | ("h3", true) |
+            ("h4", true) | ("h5", true) | ("h6", true) => {
+                self.heading = None;
+                self.blank_line();
+            },
+            ("p", _) | ("div", _) | ("table", _) | ("details", _) => self.blank_line(),
+            ("br", _) => self.newline(),
+            ("hr", _) => {
+                self.blank_line();
+                self.write(&"─".repeat(40).bright_black().to_string());
+                self.blank_line();
+            },
+            ("ul", false) => { self.newline(); self.lists.push(None); },
+            ("ol", false) => {
+                self.newline();
+                let start = attr(tag, "start").and_then(|s| s.parse().ok()).unwrap_or(1);
+                self.lists.push(Some(start));
+            },
+            ("ul", true) | ("ol", true) => {
+                self.lists.pop();
+                if self.lists.is_empty() { self.blank_line(); } else { self.newline(); }
+            },
+            ("li", false) => {
+                self.newline();
+                self.bullet = Some(match self.lists.last_mut() {
+                    Some(Some(n)) => { *n += 1; format!("{}.", *n - 1) },
+                    _ => "•".to_string(),
+                });
+            },
+            ("li", true) => self.newline(),
+            ("blockquote", false) => { self.blank_line(); self.quotes += 1; },
+            ("blockquote", true) => { self.quotes = self.quotes.saturating_sub(1); self.blank_line(); },
+            ("pre", false) => { self.blank_line(); self.pre = true; },
+            ("pre", true) => { self.pre = false; self.blank_line(); },
+            ("code", false) => self.code += 1,
+            ("code", true) => self.code = self.code.saturating_sub(1),
+            ("strong", false) | ("b", false) => self.bold += 1,
+            ("strong", true) | ("b", true) => self.bold = self.bold.saturating_sub(1),
+            ("em", false) | ("i", false) => self.italic += 1,
+            ("em", true) | ("i", true) => self.italic = self.italic.saturating_sub(1),
+            ("a", false) => {
+                let href = attr(tag, "href").unwrap_or_default();
+                self.links.push((href, self.out.len()));
+            },
+            ("a", true) => if let Some((href, start)) = self.links.pop() {
+                // show the url unless it's an anchor, or it's already the text
+                if !href.is_empty() && !href.starts_with('#') && !self.out[start..].contains(&href) {
+                    self.space = true;
+                    self.text(&format!("({})", href));
+                }
+            },
+            ("img", _) => {
+                let alt = attr(tag, "alt").unwrap_or_default();
+                if !alt.is_empty() {
+                    self.text(&format!("[{}]", alt));
+                }
+            },
+            ("tr", false) => { self.newline(); self.cells = 0; },
+            ("td", false) | ("th", false) => {
+                if self.cells > 0 { self.write(&" │ ".bright_black().to_string()); }
+                self.cells += 1;
+                if name == "th" { self.bold += 1; }
+            },
+            ("th", true) => self.bold = self.bold.saturating_sub(1),
+            _ => (),
+        }
+    }
+
+    /// Writes some text, collapsing its whitespace unless it's preformatted
+    fn text(&mut self, text: &str) {
+        if self.skip > 0 {
+            return;
+        }
+        if self.pre {
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 { self.newline(); }
+                if !line.is_empty() {
+                    self.write(&format!("    {}", line).green().to_string());
+                }
+            }
+            return;
+        }
+
+        if text.starts_with(char::is_whitespace) {
+            self.space = true;
+        }
+        for word in text.split_whitespace() {
+            if self.space && !self.line_start {
+                self.out.push(' ');
+            }
+            let styled = self.style(word);
+            self.write(&styled);
+            self.space = true;
+        }
+        if !text.ends_with(char::is_whitespace) {
+            self.space = false;
+        }
+    }
+
+    /// Styles a word as the open elements require
+    fn style(&self, word: &str) -> String {
+        let mut s = word.normal();
+        if self.heading.is_some() {
+            s = match self.heading { Some(1) => s.bright_blue().bold().underline(),
+                _ => s.bright_blue().bold() };
+        } else if self.code > 0 {
+            s = s.yellow();
+        } else if !self.links.is_empty() {
+            s = s.cyan().underline();
+        }
+        if self.bold > 0 { s = s.bold(); }
+        if self.italic > 0 { s = s.italic(); }
+        s.to_string()
+    }
+
+    /// Writes already styled text, indenting it at the start of a line
+    /// as the open quotes and lists require
+    fn write(&mut self, text: &str) {
+        if self.line_start {
+            self.out.push_str(&"│ ".repeat(self.quotes).bright_black().to_string());
+            let depth = self.lists.len();
+            match self.bullet.take() {
+                Some(bullet) => self.out.push_str(&format!("{}{} ",
+                    "  ".repeat(depth.saturating_sub(1)), bullet.bright_black())),
+                None => self.out.push_str(&"  ".repeat(depth)),
+            }
+            self.line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    /// Ends the current line, if it's not empty
+    fn newline(&mut self) {
+        if !self.out.is_empty() && !self.line_start {
+            self.out.push('\n');
+        }
+        self.line_start = true;
+        self.space = false;
+    }
+
+    /// Ends the current line, leaving a blank line after it
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+}
+
+/// Returns the value of an attribute of a tag
+fn attr(tag: &str, name: &str) -> Option<String> {
+    for quote in ['"', '\''].iter() {
+        let key = format!(" {}={}", name, quote);
+        if let Some(start) = tag.find(&key).map(|s| s + key.len()) {
+            let end = tag[start..].find(*quote)?;
+            return Some(decode(&tag[start..start + end]));
+        }
+    }
+    None
+}
+
+/// Decodes the HTML entities of a text
+fn decode(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => { out.push('&'); rest = &rest[1..]; continue; }
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                u32::from_str_radix(&entity[2..], 16).ok().and_then(std::char::from_u32)
+            },
+            _ if entity.starts_with('#') => {
+                entity[1..].parse().ok().and_then(std::char::from_u32)
+            },
+            _ => None,
+        };
+        match decoded {
+            Some(c) => { out.push(c); rest = &rest[end + 1..]; },
+            None => { out.push('&'); rest = &rest[1..]; },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders without the colors, to compare just the text
+    fn plain(html: &str) -> String {
+        colored::control::set_override(false);
+        render(html)
+    }
+
+    #[test]
+    fn headings_and_paragraphs() {
+        assert_eq!(plain("<h1>Title</h1><p>Some text.</p><h2>Usage</h2>"),
+            "# Title\n\nSome text.\n\n## Usage\n");
+        assert_eq!(plain("<blockquote><p>quoted\ntext</p></blockquote>"), "│ quoted text\n");
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(plain("<ul>\n<li>one</li>\n<li>two\n<ul>\n<li>nested</li>\n</ul>\n</li>\n</ul>\n\
+            <ol start=\"3\">\n<li>three</li>\n<li>four</li>\n</ol>\n<p>After</p>"),
+            "• one\n• two\n  • nested\n\n3. three\n4. four\n\nAfter\n");
+    }
+
+    #[test]
+    fn code() {
+        assert_eq!(plain("<p>Run:</p>\n<pre><code>fn main() {\n    println!(&quot;hi&quot;);\n}\n\
+            </code></pre>\n<p>Or <code>cargo run</code> it.</p>"),
+            "Run:\n\n    fn main() {\n        println!(\"hi\");\n    }\n\nOr cargo run it.\n");
+    }
+
+    #[test]
+    fn links_and_tables() {
+        assert_eq!(plain("<p>See <a href=\"https://docs.rs/crin\">the docs</a>, \
+            <a href=\"#usage\">usage</a> and \
+            <a href=\"https://crates.io\">https://crates.io</a>.</p>"),
+            "See the docs (https://docs.rs/crin), usage and https://crates.io.\n");
+        assert_eq!(plain("<table>\n<thead><tr><th>a</th><th>b</th></tr></thead>\n\
+            <tbody><tr><td>1</td><td>2</td></tr></tbody>\n</table>"),
+            "a │ b\n1 │ 2\n");
+    }
+
+    #[test]
+    fn entities_comments_and_scripts() {
+        assert_eq!(plain("<p>a &amp; b &#x27;c&#39; &unknown; &lt;T&gt;</p>\
+            <!-- hidden --><script>var x = 1;</script><p>end</p>"),
+            "a & b 'c' &unknown; <T>\n\nend\n");
+    }
+}
@@ -0,0 +1,152 @@
+use super::{APPNAME, VERSION};
+
+use std::cell::Cell;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use reqwest::header::USER_AGENT;
+use serde_json::Value;
+
+use crate::cache::Cache;
+use crate::conf::Config;
+
+/// The minimum time between requests, as asked by the crates.io crawler policy
+const RATE_LIMIT: Duration = Duration::from_millis(1000);
+
+/// The maximum number of results per page allowed by the API
+pub const PER_PAGE: u64 = 100;
+
+/// Makes requests to the registry API configured in `registry`,
+/// for the endpoints not covered by `crates_io_api`
+pub struct Registry {
+    client: reqwest::Client,
+    /// the url of the API, e.g. `https://crates.io/api/v1`
+    api: String,
+    /// when the last request was made
+    last: Cell<Option<Instant>>,
+}
+
+impl Registry {
+
+    pub fn new() -> Registry {
+        Registry {
+            client: reqwest::Client::new(),
+            api: format!("{}/api/v1", Config::current().registry),
+            last: Cell::new(None),
+        }
+    }
+
+    /// Returns the JSON response of an API path, e.g. `users/joseluis`,
+    /// which is reused for `cache.responses_ttl` seconds
+    pub fn get(&self, path: &str) -> Result<Value, String> {
+        let url = format!("{}/{}", self.api, path);
+        if let Some(cached) = Cache::response(&url) {
+            return Ok(cached);
+        }
+
+        let res = self.request(path)?.json::<Value>()
+            .map_err(|e| format!("invalid response from {}: {}", path, e))?;
+        Cache::store_response(&url, &res);
+        Ok(res)
+    }
+
+    /// Returns the text response of an API path
+    pub fn get_text(&self, path: &str) -> Result<String, String> {
+        self.request(path)?.text()
+            .map_err(|e| format!("invalid response from {}: {}", path, e))
+    }
+
+    /// Returns the profile of a user
+    pub fn user(&self, login: &str) -> Result<Value, String> {
+        Ok(self.get(&format!("users/{}", login))?.get("user").cloned().unwrap_or_default())
+    }
+
+    /// Returns the profile of a team, e.g. `github:rust-lang:libs`
+    pub fn team(&self, name: &str) -> Result<Value, String> {
+        Ok(self.get(&format!("teams/{}", name))?.get("team").cloned().unwrap_or_default())
+    }
+
+    /// Returns all the crates owned by a user or a team,
+    /// where `owner` is either `user_id` or `team_id`
+    pub fn owned_crates(&self, owner: &str, id: u64) -> Result<Vec<Value>, String> {
+        self.get_all(&format!("crates?{}={}", owner, id), "crates")
+    }
+
+    /// Returns a page of the reverse dependencies of a crate, with the total number of them.
+    ///
+    /// Each dependency is paired with the version of the dependent crate.
+    pub fn reverse_dependencies(&self, name: &str, page: u64)
+        -> Result<(Vec<(Value, Value)>, u64), String> {
+
+        let mut res = self.get(&format!("crates/{}/reverse_dependencies?page={}&per_page={}",
+            name, page, PER_PAGE))?;
+        let total = res["meta"]["total"].as_u64().unwrap_or(0);
+
+        let versions = res.get_mut("versions").map(Value::take).unwrap_or_default();
+        let pairs = match res.get_mut("dependencies").map(Value::take) {
+            Some(Value::Array(deps)) => deps.into_iter().map(|d| {
+                let version = versions.as_array()
+                    .and_then(|vs| vs.iter().find(|v| v["id"] == d["version_id"]))
+                    .cloned().unwrap_or_default();
+                (d, version)
+            }).collect(),
+            _ => Vec::new(),
+        };
+        Ok((pairs, total))
+    }
+
+    /// Returns all the items of a paginated API path, from all its pages
+    pub fn get_all(&self, path: &str, key: &str) -> Result<Vec<Value>, String> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+
+        for page in 1.. {
+            let mut res = self.get(&format!("{}{}page={}&per_page={}",
+                path, separator, page, PER_PAGE))?;
+            let total = res["meta"]["total"].as_u64().unwrap_or(0);
+
+            match res.get_mut(key).map(Value::take) {
+                Some(Value::Array(page_items)) if !page_items.is_empty() => {
+                    items.extend(page_items)
+                },
+                _ => break,
+            }
+            if items.len() as u64 >= total {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Makes a GET request to an API path, waiting for the rate limit
+    fn request(&self, path: &str) -> Result<reqwest::Response, String> {
+        if let Some(last) = self.last.get() {
+            let elapsed = last.elapsed();
+            if elapsed < RATE_LIMIT {
+                thread::sleep(RATE_LIMIT - elapsed);
+            }
+        }
+        self.last.set(Some(Instant::now()));
+
+        let url = format!("{}/{}", self.api, path);
+        let res = self.client.get(&url)
+            .header(USER_AGENT, format!("{}/{} ({})", APPNAME, VERSION,
+                env!("CARGO_PKG_REPOSITORY")))
+            .send()
+            .map_err(|e| format!("couldn't connect to {}: {}", url, e))?;
+
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(format!("{} returned {}", url, res.status()))
+        }
+    }
+}
+
+/// Parses a date of a JSON response
+pub fn date(value: &Value) -> Option<DateTime<Utc>> {
+    value.as_str()
+        .and_then(|d| DateTime::parse_from_rfc3339(d).ok())
+        .map(|d| d.with_timezone(&Utc))
+}
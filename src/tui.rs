@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+use crates_io_api::Crate;
+use crossterm::{execute, queue, Result};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use num_format::{Locale, ToFormattedString};
+
+use crate::actions::{crate_fields, Actuator};
+use crate::conf::{Config, Lists};
+use crate::util::open_url;
+
+/// Milliseconds without typing before the query is searched
+const DEBOUNCE: u64 = 400;
+
+const HELP: &str = "↑↓ select  Enter details  ^A add to list  ^O repository  ^D docs  Esc quit";
+
+/// The full-screen interactive browser of search results
+pub struct Tui {
+    act: Actuator,
+    query: String,
+    /// true when the query changed and hasn't been searched yet
+    dirty: bool,
+    results: Vec<Crate>,
+    total: u64,
+    selected: usize,
+    /// the first result shown, for scrolling
+    offset: usize,
+    /// the complete details of the crates already fetched
+    details: HashMap<String, Vec<(&'static str, String)>>,
+    /// the name of the list being typed, when adding a crate
+    prompt: Option<String>,
+    status: String,
+}
+
+impl Tui {
+
+    pub fn new(query: Option<&str>) -> Tui {
+        // nothing can be printed over the interface
+        let act = Actuator::new();
+        act.silence_warnings();
+
+        Tui {
+            act,
+            query: query.unwrap_or("").to_string(),
+            dirty: true,
+            results: Vec::new(),
+            total: 0,
+            selected: 0,
+            offset: 0,
+            details: HashMap::new(),
+            prompt: None,
+            status: String::new(),
+        }
+    }
+
+    /// Runs the interface until the user quits, restoring the terminal afterwards
+    pub fn run(&mut self) -> Result<()> {
+        let mut out = io::stdout();
+        let _screen = Screen::enter(&mut out)?;
+        self.event_loop(&mut out)
+    }
+
+    fn event_loop(&mut self, out: &mut Stdout) -> Result<()> {
+        loop {
+            self.draw(out)?;
+
+            // search once the user pauses typing
+            if self.dirty && !event::poll(Duration::from_millis(DEBOUNCE))? {
+                self.search();
+                continue;
+            }
+            // other events, like resizing, just redraw
+            if let Event::Key(key) = event::read()? {
+                if !self.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Searches the current query, replacing the results
+    fn search(&mut self) {
+        self.dirty = false;
+        self.selected = 0;
+        self.offset = 0;
+
+        let query = if self.query.trim().is_empty() { None } else { Some(self.query.trim()) };
+        match self.act.find_crates(query, 1, Config::current().per_page) {
+            Ok((crates, total)) => {
+                self.results = crates;
+                self.total = total;
+                self.status = String::new();
+            },
+            Err(e) => {
+                self.results.clear();
+                self.total = 0;
+                self.status = format!("Error: {}", e);
+            }
+        }
+    }
+
+    /// Handles a key press, returning false when the user quits
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        // typing the list where to add the selected crate
+        if let Some(mut list) = self.prompt.take() {
+            match key.code {
+                KeyCode::Esc => self.status = String::new(),
+                KeyCode::Enter => self.add_selected(list.trim()),
+                KeyCode::Tab => {
+                    let names = Lists::names();
+                    let next = names.iter().position(|n| *n == list)
+                        .map(|i| (i + 1) % names.len())
+                        .or_else(|| names.iter().position(|n| n.starts_with(&list)));
+                    if let Some(i) = next { list = names[i].clone(); }
+                    self.prompt = Some(list);
+                },
+                KeyCode::Backspace => { list.pop(); self.prompt = Some(list); },
+                KeyCode::Char(c) if !ctrl => { list.push(c); self.prompt = Some(list); },
+                _ => self.prompt = Some(list),
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::PageUp => self.select(-10),
+            KeyCode::PageDown => self.select(10),
+            KeyCode::Home => self.select(-(self.results.len() as isize)),
+            KeyCode::End => self.select(self.results.len() as isize),
+            KeyCode::Enter => self.fetch_details(),
+            KeyCode::Char('a') if ctrl => if !self.results.is_empty() {
+                self.prompt = Some(String::new());
+            },
+            KeyCode::Char('o') if ctrl => self.open(false),
+            KeyCode::Char('d') if ctrl => self.open(true),
+            KeyCode::Backspace => { self.query.pop(); self.dirty = true; },
+            KeyCode::Char(c) if !ctrl => { self.query.push(c); self.dirty = true; },
+            _ => (),
+        }
+        true
+    }
+
+    /// Moves the selection up (negative) or down
+    fn select(&mut self, delta: isize) {
+        if self.results.is_empty() { return; }
+        let last = self.results.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).max(0).min(last) as usize;
+    }
+
+    /// Fetches the complete details of the selected crate, if they weren't already
+    fn fetch_details(&mut self) {
+        if let Some(c) = self.results.get(self.selected) {
+            if !self.details.contains_key(&c.name) {
+                match self.act.crate_details(&c.name, true) {
+                    Ok(details) => { self.details.insert(c.name.clone(), details); },
+                    Err(e) => self.status = format!("Error: {}", e),
+                }
+            }
+        }
+    }
+
+    /// Adds the selected crate to a list, creating the list if it doesn't exist
+    fn add_selected(&mut self, list: &str) {
+        let name = match self.results.get(self.selected) {
+            Some(c) => c.name.clone(),
+            None => return,
+        };
+        if list.is_empty() {
+            self.status = String::new();
+            return;
+        }
+        let mut crates = match Lists::crates(list) {
+            Some(crates) => crates,
+            // replacing an invalid list would lose its contents
+            None if Lists::exists(list) => {
+                self.status = format!("Error: the list \"{}\" is not valid, \
+                    repair it with 'crin config doctor --fix'", list);
+                return;
+            },
+            None => Vec::new(),
+        };
+        if crates.contains(&name) {
+            self.status = format!("Crate \"{}\" is already in list \"{}\"", name, list);
+        } else {
+            crates.push(name.clone());
+            // the errors are shown in the status line, since nothing can be printed
+            self.status = match Lists::try_set(list, &crates) {
+                Ok(_) => format!("Added crate \"{}\" to the list \"{}\"", name, list),
+                Err(e) => e,
+            };
+        }
+    }
+
+    /// Opens the repository or the documentation of the selected crate
+    fn open(&mut self, docs: bool) {
+        let c = match self.results.get(self.selected) {
+            Some(c) => c,
+            None => return,
+        };
+        let url = if docs {
+            Some(c.documentation.clone().unwrap_or(format!("https://docs.rs/{}", c.name)))
+        } else {
+            c.repository.clone().or(c.homepage.clone())
+        };
+        self.status = match url {
+            Some(url) => match open_url(&url) {
+                Ok(_) => format!("Opened {}", url),
+                Err(e) => format!("Error: couldn't open {}: {}", url, e),
+            },
+            None => format!("Crate \"{}\" has no repository", c.name),
+        };
+    }
+
+    /// Returns the details of the selected crate: the complete ones if they
+    /// were fetched, or else the ones included in the search results
+    fn selected_details(&self) -> Vec<(&'static str, String)> {
+        let c = match self.results.get(self.selected) {
+            Some(c) => c,
+            None => return Vec::new(),
+        };
+        if let Some(details) = self.details.get(&c.name) {
+            return details.clone();
+        }
+
+        let mut details = crate_fields(c, &[], None);
+        details.push(("", "(press Enter for more)".to_string()));
+        details
+    }
+
+    fn draw(&mut self, out: &mut Stdout) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        let rows = height.saturating_sub(3);
+        let left = (width * 2 / 5).max(20).min(width);
+
+        // keep the selection visible
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if rows > 0 && self.selected >= self.offset + rows {
+            self.offset = self.selected + 1 - rows;
+        }
+
+        queue!(out, Clear(ClearType::All))?;
+
+        // query
+        let searching = if self.dirty { "…".to_string() } else {
+            format!("{} results", self.total.to_formatted_string(&Locale::en))
+        };
+        put(out, 0, 0, "Search: ", Some(Color::Blue), Some(Attribute::Bold))?;
+        put(out, 8, 0, &fit(&format!("{}_", self.query), width.saturating_sub(8)), None, None)?;
+        if width > searching.len() + self.query.len() + 12 {
+            put(out, width - searching.len(), 0, &searching, Some(Color::DarkGrey), None)?;
+        }
+
+        // results
+        for (row, c) in self.results.iter().enumerate().skip(self.offset).take(rows) {
+            let y = row - self.offset + 2;
+            let line = fit(&format!("{} {}", c.name, c.max_version), left.saturating_sub(1));
+            if row == self.selected {
+                put(out, 0, y, &format!("{:w$}", line, w = left.saturating_sub(1)),
+                    Some(Color::Green), Some(Attribute::Reverse))?;
+            } else {
+                put(out, 0, y, &line, Some(Color::Green), None)?;
+            }
+        }
+
+        // details of the selected crate
+        let label_width = 15;
+        let value_width = width.saturating_sub(left + 1 + label_width);
+        let mut y = 2;
+        for (label, value) in self.selected_details() {
+            for (i, line) in wrap(&value, value_width).iter().enumerate() {
+                if y + 1 >= height { break; }
+                if i == 0 && !label.is_empty() {
+                    put(out, left + 1, y, &format!("{}:", label), Some(Color::Blue), None)?;
+                }
+                let color = if label.is_empty() { Color::DarkGrey } else { Color::Reset };
+                put(out, left + 1 + label_width, y, line, Some(color), None)?;
+                y += 1;
+            }
+        }
+
+        // status line
+        let bottom = height.saturating_sub(1);
+        if let Some(ref list) = self.prompt {
+            let name = self.results.get(self.selected).map(|c| c.name.as_str()).unwrap_or("");
+            put(out, 0, bottom, &fit(&format!("Add \"{}\" to list (Tab completes): {}_",
+                name, list), width), Some(Color::Yellow), None)?;
+        } else if !self.status.is_empty() {
+            put(out, 0, bottom, &fit(&self.status, width), Some(Color::Yellow), None)?;
+        } else {
+            put(out, 0, bottom, &fit(HELP, width), Some(Color::DarkGrey), None)?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// The raw mode and the alternate screen, which are left when dropped,
+/// so that the terminal is restored even after an error or a panic
+struct Screen;
+
+impl Screen {
+    fn enter(out: &mut Stdout) -> Result<Screen> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Prints a text at a position, with an optional color and attribute
+fn put(out: &mut Stdout, x: usize, y: usize, text: &str,
+    color: Option<Color>, attr: Option<Attribute>) -> Result<()> {
+
+    queue!(out, MoveTo(x as u16, y as u16))?;
+    if let Some(color) = color { queue!(out, SetForegroundColor(color))?; }
+    if let Some(attr) = attr { queue!(out, SetAttribute(attr))?; }
+    queue!(out, Print(text), SetAttribute(Attribute::Reset), ResetColor)?;
+    Ok(())
+}
+
+/// Truncates a text to a number of characters
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut s: String = text.chars().take(width.saturating_sub(1)).collect();
+        s.push('…');
+        s
+    }
+}
+
+/// Splits a text in lines of up to a number of characters, between words
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let len = lines.last().unwrap().chars().count();
+        if len > 0 && len + 1 + word.chars().count() > width {
+            lines.push(String::new());
+        }
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() { line.push(' '); }
+        line.push_str(&fit(word, width));
+    }
+    lines
+}
@@ -1,13 +1,49 @@
-use chrono::{DateTime, Local, Utc};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use colored::*;
 
 
-/// Format a DateTime as either YYYY-MM-DD or with a custom format
-pub fn date_str(date: &DateTime<Utc>, format: &str) -> String {
-    if format.is_empty() {
-        date.with_timezone(&Local).date().naive_local().to_string()
+lazy_static! {
+    static ref DATES: RwLock<DateDisplay> = RwLock::new(DateDisplay {
+        mode: DateMode::Both,
+        utc: false,
+        format: "%Y-%m-%d".to_string(),
+    });
+}
+
+/// How the dates are shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DateMode {
+    /// e.g. `2019-04-21`
+    Absolute,
+    /// e.g. `3 days ago`
+    Relative,
+    /// e.g. `2019-04-21 (3 days ago)`
+    Both,
+}
+
+struct DateDisplay {
+    mode: DateMode,
+    utc: bool,
+    format: String,
+}
+
+/// Configure how the dates are shown, in local time or UTC, and their format
+pub fn set_date_display(mode: DateMode, utc: bool, format: &str) {
+    *DATES.write().unwrap() = DateDisplay { mode, utc, format: format.to_string() };
+}
+
+
+/// Format a DateTime with the configured format, in local time or UTC
+pub fn date_str(date: &DateTime<Utc>) -> String {
+    let dates = DATES.read().unwrap();
+    if dates.utc {
+        date.format(&dates.format).to_string()
     } else {
-        date.with_timezone(&Local).format(format).to_string()
+        date.with_timezone(&Local).format(&dates.format).to_string()
     }
 }
 
@@ -23,6 +59,55 @@ pub fn date_ago(date: &DateTime<Utc>, num_items: usize) -> String {
 }
 
 
+/// Show a date as configured: absolute, relative or both
+pub fn date_show(date: &DateTime<Utc>) -> String {
+    let mode = DATES.read().unwrap().mode;
+    match mode {
+        DateMode::Absolute => date_str(date),
+        DateMode::Relative => date_ago(date, 1).trim_matches(|c| c == '(' || c == ')').to_string(),
+        DateMode::Both => format!("{} {}", date_str(date), date_ago(date, 1)),
+    }
+}
+
+
+/// Parse a date given as RFC 3339 or as `YYYY-MM-DD` (at midnight UTC)
+pub fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date).map(|d| d.with_timezone(&Utc)).ok()
+        .or_else(|| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+            .map(|d| DateTime::from_utc(d.and_hms(0, 0, 0), Utc)))
+}
+
+
+/// Format a DateTime as RFC 3339, for the machine-readable outputs
+pub fn date_rfc3339(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339()
+}
+
+
+/// Return a sparkline of the values, scaled so that `max` is the highest bar
+pub fn sparkline(values: &[u64], max: u64) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    values.iter().map(|&v| {
+        if max == 0 {
+            BARS[0]
+        } else {
+            BARS[((v.min(max) * 7 + max / 2) / max) as usize]
+        }
+    }).collect()
+}
+
+
+/// Return a horizontal bar of up to `width` characters, proportional to the fraction
+pub fn bar(fraction: f64, width: usize) -> String {
+    let eighths = (fraction.max(0.0).min(1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if eighths % 8 > 0 {
+        bar.push(['▏', '▎', '▍', '▌', '▋', '▊', '▉'][eighths % 8 - 1]);
+    }
+    bar
+}
+
+
 /// Return a string of words separated by commas,
 /// optionally surrounding each word with a string.
 pub fn commify(words: Vec<&str>, surround: &str,
@@ -38,3 +123,140 @@ pub fn commify(words: Vec<&str>, surround: &str,
     }
     return text[1..].trim().to_string(); // remove the leading comma
 }
+
+
+/// Returns true if the text matches the pattern, ignoring the case.
+///
+/// Patterns containing `*` or `?` are matched as globs against the whole text,
+/// otherwise as a substring.
+pub fn pattern_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+
+    if !pattern.contains('*') && !pattern.contains('?') {
+        return text.contains(&pattern);
+    }
+
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    // the last position of a `*` in the pattern and in the text, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+
+/// Asks a yes/no question on the terminal, returning true only if answered yes
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    match answer.trim().to_lowercase().as_ref() {
+        "y" | "yes" => true,
+        _ => false,
+    }
+}
+
+
+/// Returns true if the name is valid for a crate in crates.io:
+/// up to 64 ASCII alphanumeric characters, `-` or `_`, starting with a letter
+pub fn is_valid_crate_name(name: &str) -> bool {
+    name.len() <= 64
+        && name.chars().next().map_or(false, |c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+
+/// Opens an url in the default browser of the system
+pub fn open_url(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.args(&["/C", "start", ""]);
+        c
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url).stdout(Stdio::null()).stderr(Stdio::null()).spawn().map(|_| ())
+}
+
+
+/// Prints a text through the pager in `$PAGER` (default: `less -R`)
+/// when the standard output is a terminal it doesn't fit in,
+/// or directly if there's no terminal or the pager can't be run
+pub fn page(text: &str) {
+    use crossterm::tty::IsTty;
+
+    let rows = crossterm::terminal::size().map(|(_, rows)| rows as usize).unwrap_or(0);
+    if !io::stdout().is_tty() || text.lines().count() < rows {
+        print!("{}", text);
+        return;
+    }
+
+    let pager = std::env::var("PAGER").ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let mut command = Command::new(parts.next().unwrap_or("less"));
+    command.args(parts).stdin(Stdio::piped());
+
+    match command.spawn() {
+        Ok(mut child) => {
+            // the pager may be quit before reading everything, and it
+            // needs its input closed to know where the text ends
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let _ = child.wait();
+        },
+        Err(_) => print!("{}", text),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_match_substring() {
+        assert!(pattern_match("serde", "serde_json"));
+        assert!(pattern_match("JSON", "serde_json"));
+        assert!(pattern_match("", "clap"));
+        assert!(!pattern_match("toml", "serde_json"));
+    }
+
+    #[test]
+    fn pattern_match_glob() {
+        assert!(pattern_match("serde*", "serde_json"));
+        assert!(pattern_match("*json", "serde_json"));
+        assert!(pattern_match("s?rde_*", "serde_json"));
+        assert!(pattern_match("*-*-*", "a-b-c-d"));
+        assert!(pattern_match("**", ""));
+        // matched against the whole name, unlike a substring
+        assert!(!pattern_match("serde?", "serde_json"));
+        assert!(!pattern_match("json*", "serde_json"));
+        assert!(!pattern_match("*x*", "serde_json"));
+    }
+}
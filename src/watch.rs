@@ -0,0 +1,82 @@
+use super::{ORGANIZATION, APPNAME};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::registry;
+use crate::util::date_rfc3339;
+
+const STATEFILE: &'static str = "watch.json";
+
+/// The last version and update date seen of a crate
+pub type Seen = HashMap<String, (String, DateTime<Utc>)>;
+
+/// Container of methods to manage the state of the watched lists:
+/// the last version and update date seen of each of their crates
+pub struct Watch {}
+
+impl Watch {
+
+    /// Returns the data directory, which is [OS dependant](https://crates.io/crates/directories)
+    pub fn dir() -> PathBuf {
+        directories::ProjectDirs::from("rs", ORGANIZATION, APPNAME)
+        .expect("Unable to retrieve app data directory").data_dir().to_owned()
+    }
+
+    /// Returns the path to the state file
+    pub fn file() -> PathBuf {
+        Self::dir().join(STATEFILE)
+    }
+
+    /// Returns what was seen of the crates of a list in the last check
+    pub fn seen(list: &str) -> Seen {
+        let state = Self::read();
+        let mut seen = Seen::new();
+
+        if let Some(crates) = state[list].as_object() {
+            for (name, c) in crates.iter() {
+                if let (Some(version), Some(updated)) =
+                    (c["version"].as_str(), registry::date(&c["updated_at"])) {
+                    seen.insert(name.to_string(), (version.to_string(), updated));
+                }
+            }
+        }
+        seen
+    }
+
+    /// Saves what was seen of the crates of a list, replacing the previous state
+    pub fn save(list: &str, seen: &Seen) -> io::Result<()> {
+        let mut state = Self::read();
+        if !state.is_object() {
+            state = json!({});
+        }
+
+        let crates: serde_json::Map<String, Value> = seen.iter().map(|(name, (version, updated))| {
+            (name.to_string(), json!({
+                "version": version,
+                "updated_at": date_rfc3339(updated),
+            }))
+        }).collect();
+        state[list] = Value::Object(crates);
+
+        // written to a temporary file first, so that it's never left half written
+        let contents = serde_json::to_string_pretty(&state)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let tmp = Self::dir().join(format!(".{}.tmp", STATEFILE));
+        fs::create_dir_all(Self::dir())?;
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, Self::file())
+    }
+
+    /// Reads the state of all the lists
+    fn read() -> Value {
+        fs::read_to_string(Self::file()).ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or(json!({}))
+    }
+}